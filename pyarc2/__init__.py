@@ -1,18 +1,31 @@
 from .pyarc2 import InstrumentLL as _InstrumentLL
 from .pyarc2 import BiasOrder, ControlMode, DataMode, ReadType, WaitFor, AuxDACFn
 from .pyarc2 import ReadAt, ReadAfter, ArC2Error, IODir, LogicLevel, OutputRange
+from .pyarc2 import WeightMap, MACEncoding, SafetyLimits, SafetyLimitError
+from .pyarc2 import ChannelMode, ChannelState, Snapshot, TriggerEdge
+from .pyarc2 import Calibration, ChannelReport, SelfTestReport, CrosspointReport
 from .pyarc2 import find_ids
+from .pyarc2 import save_map, load_map, save_trace, load_trace
 try:
     from .pyarc2 import LIBARC2_VERSION
 except (AttributeError, ImportError):
     LIBARC2_VERSION = None
 
+try:
+    # only available when built with the ``arrow`` feature
+    from .pyarc2 import ArrowWriter
+except ImportError:
+    pass
+
 from collections.abc import Iterable
 from dataclasses import dataclass
 from functools import partial
 from enum import Enum
 import numpy as np
 from ._types import *
+from ._measurement import Measurement, _metadata, _crossing, BITLINES, WORDLINES
+from ._journal import Journal, read_journal
+from ._replay import ReplayInstrument, ReplayError
 
 
 def _inheritdocs(fromfn: Callable, sep: str="\n"):
@@ -72,6 +85,37 @@ class ArC2Config:
     controlMode: ControlMode
 
 
+# Methods that are never recorded in a journal
+_UNJOURNALED = frozenset(['start_journal', 'stop_journal', 'port', 'firmware'])
+
+
+def _journaled_getattribute(self, name):
+    attr = object.__getattribute__(self, name)
+    if name.startswith('_') or name in _UNJOURNALED or not callable(attr):
+        return attr
+    journal = object.__getattribute__(self, '__dict__').get('_journal')
+    # only the outermost call is recorded
+    if journal is None or journal.depth > 0:
+        return attr
+    return journal.wrap(self, name, attr)
+
+
+_JOURNALED_CLASSES: dict = {}
+
+
+def _journaled_class(cls: type) -> type:
+    # Recording is done by swapping the class of a journaled instrument for
+    # a subclass that wraps method lookups, so that attribute access on
+    # instruments without an active journal is not slowed down.
+    if cls not in _JOURNALED_CLASSES:
+        _JOURNALED_CLASSES[cls] = type(cls.__name__, (cls, ), {
+            '__module__': cls.__module__,
+            '__qualname__': cls.__qualname__,
+            '__getattribute__': _journaled_getattribute,
+            '_unjournaled_class': cls })
+    return _JOURNALED_CLASSES[cls]
+
+
 class Instrument(_InstrumentLL):
     """
     To do anything with ArC TWO you will need first to instantiate an
@@ -97,6 +141,102 @@ class Instrument(_InstrumentLL):
 
     def __init__(self, port: int, firmware: str, init=True):
         _InstrumentLL.__init__(port, firmware, init)
+        self._structured = False
+        self._journal = None
+
+    def start_journal(self, path: str) -> None:
+        """
+        Start recording every command issued to the instrument into a JSON
+        Lines file. Each line records the method called, its arguments, when
+        it started, its duration and the data it returned or the error it
+        raised, so ``execute`` lines mark when command buffers were sent to
+        the instrument. The file is opened for appending and every line is
+        flushed as soon as it is written, so the journal survives crashes.
+        Journals are read back with :func:`~pyarc2.read_journal`. Starting a
+        new journal closes the previous one.
+
+        Recording happens at the Python level: only the methods called on
+        this ``Instrument`` are recorded and, when a method calls other
+        instrument methods internally, only the outermost call is. Commands
+        issued through any other reference to the underlying instrument, or
+        from code that looks up a method before the journal is started, are
+        not recorded. While a journal is active the instrument is an
+        instance of a subclass of its original class that does the
+        recording; it reverts to its original class when the journal is
+        stopped.
+
+        >>> arc.start_journal('experiment.jsonl')
+        >>> arc.pulseread_one(3, 20, 2.0, 1000, 0.2)
+        >>> arc.stop_journal()
+
+        :param str path: The journal file
+        """
+        self.stop_journal()
+        self._journal = Journal(path, { 'port': self.port(),
+            'firmware': self.firmware(), 'libarc2': LIBARC2_VERSION })
+        self.__class__ = _journaled_class(type(self))
+
+    def stop_journal(self) -> None:
+        """
+        Stop recording commands and close the journal file, if any.
+        """
+        journal = self.__dict__.get('_journal')
+        if journal is not None:
+            journal.close()
+        self._journal = None
+        self.__class__ = getattr(type(self), '_unjournaled_class', type(self))
+
+    def set_structured(self, enabled: bool) -> None:
+        """
+        Enable or disable structured results. When enabled
+        :meth:`~pyarc2.Instrument.read_slice`,
+        :meth:`~pyarc2.Instrument.read_slice_masked`,
+        :meth:`~pyarc2.Instrument.read_all` and the ``pulseread_*`` methods
+        return a :class:`~pyarc2.Measurement` carrying the parameters of the
+        operation instead of a bare numpy array. Structured results are
+        disabled by default.
+
+        :param bool enabled: Whether to return structured results
+        """
+        self._structured = enabled
+
+    def _result(self, data: Any, operation: str, **params) -> Any:
+        if not getattr(self, '_structured', False):
+            return data
+        return Measurement(data, _metadata(self, operation, **params))
+
+    @_inheritdocs(_InstrumentLL.read_slice)
+    def read_slice(self, chan: int, vread: float, baseline: bool = False) -> np.ndarray:
+        data = super().read_slice(chan, vread, baseline)
+        return self._result(data, 'read_slice', low=chan, channels=_crossing(chan),
+            vread=vread, baseline=baseline)
+
+    @_inheritdocs(_InstrumentLL.read_all)
+    def read_all(self, vread: float, order: BiasOrder, baseline: bool = False) -> np.ndarray:
+        data = super().read_all(vread, order, baseline)
+        return self._result(data, 'read_all', rows=BITLINES, cols=WORDLINES,
+            vread=vread, order=str(order), baseline=baseline)
+
+    @_inheritdocs(_InstrumentLL.pulseread_one)
+    def pulseread_one(self, low: int, high: int, vpulse: float, nanos: int,
+        vread: float) -> float:
+        data = super().pulseread_one(low, high, vpulse, nanos, vread)
+        return self._result(data, 'pulseread_one', low=low, high=high, vpulse=vpulse,
+            nanos=nanos, vread=vread)
+
+    @_inheritdocs(_InstrumentLL.pulseread_slice)
+    def pulseread_slice(self, chan: int, vpulse: float, nanos: int,
+        vread: float) -> np.ndarray:
+        data = super().pulseread_slice(chan, vpulse, nanos, vread)
+        return self._result(data, 'pulseread_slice', low=chan, channels=_crossing(chan),
+            vpulse=vpulse, nanos=nanos, vread=vread)
+
+    @_inheritdocs(_InstrumentLL.pulseread_all)
+    def pulseread_all(self, vpulse: float, nanos: int, vread: float,
+        order: BiasOrder) -> np.ndarray:
+        data = super().pulseread_all(vpulse, nanos, vread, order)
+        return self._result(data, 'pulseread_all', rows=BITLINES, cols=WORDLINES,
+            vpulse=vpulse, nanos=nanos, vread=vread, order=str(order))
 
     def _array_iter_inner(self, mode: DataMode, rtype: ReadType):
         data = self.pick_one(mode, rtype)
@@ -209,7 +349,10 @@ class Instrument(_InstrumentLL):
 
     @_inheritdocs(_InstrumentLL.read_slice_masked)
     def read_slice_masked(self, chan: int, mask: IntIterable, vread: float) -> np.ndarray:
-        return super().read_slice_masked(chan, _ndarray_check(mask), vread)
+        mask = _ndarray_check(mask)
+        data = super().read_slice_masked(chan, mask, vread)
+        return self._result(data, 'read_slice_masked', low=chan, channels=_crossing(chan),
+            mask=[int(m) for m in mask], vread=vread)
 
     @_inheritdocs(_InstrumentLL.read_slice_open)
     def read_slice_open(self, highs: IntIterable, ground_after: bool) -> np.ndarray:
@@ -228,8 +371,11 @@ class Instrument(_InstrumentLL):
     @_inheritdocs(_InstrumentLL.pulseread_slice_masked)
     def pulseread_slice_masked(self, chan: int, mask: IntIterable, vpulse: float,
         nanos: int, vread: float) -> np.ndarray:
-        return super().pulseread_slice_masked(chan, _ndarray_check(mask), vpulse,
-            nanos, vread)
+        mask = _ndarray_check(mask)
+        data = super().pulseread_slice_masked(chan, mask, vpulse, nanos, vread)
+        return self._result(data, 'pulseread_slice_masked', low=chan,
+            channels=_crossing(chan), mask=[int(m) for m in mask], vpulse=vpulse,
+            nanos=nanos, vread=vread)
 
     @_inheritdocs(_InstrumentLL.currents_from_address)
     def currents_from_address(self, addr: int, chans: IntIterable) -> np.ndarray:
@@ -260,6 +406,77 @@ class Instrument(_InstrumentLL):
         cl2: Optional[IODir] = None, cl3: Optional[IODir] = None) -> 'Instrument':
         return super().set_logic(mask, cl0, cl1, cl2, cl3)
 
+    @_inheritdocs(_InstrumentLL.set_gpio)
+    def set_gpio(self, pins: IntIterable, state: bool) -> 'Instrument':
+        i = super().set_gpio(list(pins), state)
+        return cast(Instrument, i)
+
     @_inheritdocs(_InstrumentLL.set_channel_range)
     def set_channel_range(self, chans: IntIterable, rng: OutputRange) -> 'Instrument':
         return super().set_channel_range(_ndarray_check(chans), rng)
+
+    @_inheritdocs(_InstrumentLL.fast_pulse)
+    def fast_pulse(self, channels: dict[int, tuple[float, int]], normal: float = 0.0,
+        preset_state: bool = True) -> 'Instrument':
+        i = super().fast_pulse(dict(channels), normal, preset_state)
+        return cast(Instrument, i)
+
+    def run_table(self, table: np.ndarray, vread: float = 0.2,
+        chunk: Optional[int] = None) -> np.ndarray:
+        """
+        Run a pulse program described by a numpy structured array. Every row
+        of ``table`` applies a pulse between two crosspoints, same as
+        :meth:`~pyarc2.Instrument.pulse_one`, optionally followed by a current
+        read. The table must provide the fields ``low``, ``high``, ``voltage``
+        and ``width`` (in nanoseconds) and optionally a boolean ``read`` field;
+        if ``read`` is missing no read-outs are done. Tables generated from
+        pandas can be converted with ``DataFrame.to_records(index=False)``.
+        All rows are queued in Rust and executed in chunks that fit the
+        output memory of the instrument.
+
+        >>> table = np.array([(0, 16, 1.5, 100, False), (0, 16, 1.5, 100, True)],
+        >>>     dtype=[('low', 'u8'), ('high', 'u8'), ('voltage', 'f4'),
+        >>>            ('width', 'u8'), ('read', '?')])
+        >>> currents = arc.run_table(table, 0.2) # currents.shape == (1, )
+
+        :param table: A numpy structured array with the pulse program
+        :param float vread: The read-out voltage for rows that request a read
+        :param int chunk: Number of rows per command buffer; defaults to the
+                          largest chunk that fits the output memory
+        :return: The currents of the rows that requested a read-out in the
+                 order of the rows
+        :rtype: A numpy f32 array
+        :raises ValueError: If ``chunk`` is zero or larger than the output
+                            memory allows
+        """
+
+        if table.dtype.names is None:
+            raise TypeError('Pulse table must be a numpy structured array')
+
+        missing = {'low', 'high', 'voltage', 'width'} - set(table.dtype.names)
+        if len(missing) > 0:
+            raise ValueError('Pulse table is missing fields: %s' % \
+                ', '.join(sorted(missing)))
+
+        if 'read' in table.dtype.names:
+            reads = np.ascontiguousarray(table['read'], dtype=np.bool_)
+        else:
+            reads = np.zeros(table.shape[0], dtype=np.bool_)
+
+        return super().run_table(
+            np.ascontiguousarray(table['low'], dtype=np.uint64),
+            np.ascontiguousarray(table['high'], dtype=np.uint64),
+            np.ascontiguousarray(table['voltage'], dtype=np.float32),
+            np.ascontiguousarray(table['width'], dtype=np.uint64),
+            reads, vread, chunk)
+
+    @_inheritdocs(_InstrumentLL.mac_encoded)
+    def mac_encoded(self, inp_chans: List[tuple[int, int]], out_chans: IntIterable,
+        vread: float, encoding: MACEncoding) -> np.ndarray:
+        return super().mac_encoded(inp_chans, list(out_chans), vread, encoding)
+
+    @_inheritdocs(_InstrumentLL.mvm)
+    def mvm(self, inputs: np.ndarray, weights_map: WeightMap, v_scale: float,
+        vmax: Optional[float] = None) -> np.ndarray:
+        inputs = np.ascontiguousarray(np.atleast_2d(inputs), dtype=np.float32)
+        return super().mvm(inputs, weights_map, v_scale, vmax)
@@ -0,0 +1,188 @@
+from datetime import datetime, timezone
+from functools import wraps
+import json
+import time
+import numpy as np
+from ._types import *
+from ._measurement import Measurement
+
+
+JOURNAL_VERSION = 1
+""" Version of the journal format """
+
+
+def encode(value: Any, instrument: Any = None) -> Any:
+    """
+    Convert ``value`` into something that can be serialised into JSON.
+    numpy arrays are stored as ``{"__ndarray__": {"dtype", "shape",
+    "data"}}``, dicts as a list of key/value pairs (so that non-string keys
+    survive), :class:`~pyarc2.Measurement` objects with their metadata and
+    the instrument itself (returned by chainable methods) as
+    ``{"__self__": true}``. Objects that can be serialised with ``to_json``
+    are stored as such and the enumerations and directives of pyarc2 (such
+    as :class:`~pyarc2.DataMode` or :class:`~pyarc2.WaitFor`) by their
+    string form, which names the class and variant. Anything else is
+    recorded by its type and, if it defines one, its string representation;
+    the default representation of Python objects is not recorded as it
+    includes the address of the object and would never match on replay.
+    """
+
+    if instrument is not None and value is instrument:
+        return { '__self__': True }
+    if value is None or isinstance(value, (bool, int, float, str)):
+        return value
+    if isinstance(value, np.ndarray):
+        return { '__ndarray__': { 'dtype': value.dtype.str, 'shape': list(value.shape),
+            'data': value.ravel().tolist() } }
+    if isinstance(value, np.generic):
+        return value.item()
+    if isinstance(value, Measurement):
+        return { '__measurement__': { 'data': encode(value.data),
+            'metadata': encode(value.metadata), 'times': encode(value.times) } }
+    if isinstance(value, (list, tuple)):
+        return [encode(v, instrument) for v in value]
+    if isinstance(value, dict):
+        return { '__dict__': [[encode(k), encode(v, instrument)] for (k, v) in value.items()] }
+    if hasattr(value, 'to_json'):
+        return { '__object__': type(value).__name__, 'json': value.to_json() }
+    cls = type(value)
+    if cls.__str__ is object.__str__ and cls.__repr__ is object.__repr__:
+        return { '__object__': cls.__name__ }
+    return { '__object__': cls.__name__, 'str': str(value) }
+
+
+def decode(value: Any) -> Any:
+    """
+    Reverse :func:`encode`. Objects that were recorded by their string
+    representation are returned as the ``{"__object__", "str"}`` dict they
+    were stored as.
+    """
+
+    if isinstance(value, list):
+        return [decode(v) for v in value]
+    if not isinstance(value, dict):
+        return value
+    if '__ndarray__' in value:
+        arr = value['__ndarray__']
+        return np.array(arr['data'], dtype=np.dtype(arr['dtype'])).reshape(arr['shape'])
+    if '__measurement__' in value:
+        m = value['__measurement__']
+        return Measurement(decode(m['data']), decode(m['metadata']),
+            decode(m.get('times')))
+    if '__dict__' in value:
+        return { _hashable(decode(k)): decode(v) for (k, v) in value['__dict__'] }
+    return value
+
+
+def _hashable(value: Any) -> Any:
+    if isinstance(value, list):
+        return tuple(_hashable(v) for v in value)
+    return value
+
+
+class Journal:
+    """
+    Append-only JSON Lines record of the commands issued to an instrument.
+    Journals are created with :meth:`~pyarc2.Instrument.start_journal` and
+    read back with :func:`~pyarc2.read_journal`. Every session starts with a
+    header line identifying the instrument followed by one line per command
+    with its arguments, start time, duration and either its result or the
+    error it raised. Only the outermost call is recorded when a method calls
+    other instrument methods internally.
+    """
+
+    def __init__(self, path: str, header: dict):
+        self.path = path
+        self.depth = 0
+        self._seq = 0
+        self._fh = open(path, 'a', encoding='utf-8')
+        self._write(dict({ 'journal': JOURNAL_VERSION,
+            'started': datetime.now(timezone.utc).isoformat() }, **header))
+
+    def _write(self, entry: dict):
+        self._fh.write(json.dumps(entry) + '\n')
+        self._fh.flush()
+
+    def record(self, command: str, args: tuple, kwargs: dict, started: float,
+        duration: float, instrument: Any, result: Any = None,
+        error: Optional[BaseException] = None):
+
+        entry = {
+            'seq': self._seq,
+            'command': command,
+            'args': encode(list(args), instrument),
+            'kwargs': encode(kwargs, instrument),
+            'time': datetime.fromtimestamp(started, timezone.utc).isoformat(),
+            'duration': duration
+        }
+        if error is None:
+            entry['result'] = encode(result, instrument)
+        else:
+            entry['error'] = { 'type': type(error).__name__, 'message': str(error) }
+
+        self._seq += 1
+        self._write(entry)
+
+    def wrap(self, instrument: Any, name: str, fn: Callable) -> Callable:
+
+        @wraps(fn)
+        def _recorded(*args, **kwargs):
+            started = time.time()
+            t0 = time.perf_counter()
+            self.depth += 1
+            try:
+                result = fn(*args, **kwargs)
+            except Exception as exc:
+                self.record(name, args, kwargs, started, time.perf_counter() - t0,
+                    instrument, error=exc)
+                raise
+            finally:
+                self.depth -= 1
+            self.record(name, args, kwargs, started, time.perf_counter() - t0,
+                instrument, result=result)
+            return result
+
+        return _recorded
+
+    def close(self):
+        self._fh.close()
+
+
+def read_journal(path: str, decode_values: bool = True) -> List[dict]:
+    """
+    Load a journal written by :meth:`~pyarc2.Instrument.start_journal`. As
+    journals are append-only a file can contain several sessions; each one
+    is returned as a dict with a ``header`` and a list of ``entries``.
+    Arguments and results are decoded back into Python objects, including
+    numpy arrays, unless ``decode_values`` is ``False``.
+
+    >>> sessions = read_journal('experiment.jsonl')
+    >>> for entry in sessions[-1]['entries']:
+    ...     print(entry['command'], entry.get('result'))
+
+    :param str path: The journal file
+    :param bool decode_values: Whether to decode arguments and results
+    :return: A list of sessions in the order they were recorded
+    """
+
+    sessions: List[dict] = []
+
+    with open(path, 'r', encoding='utf-8') as fh:
+        for (lineno, line) in enumerate(fh, 1):
+            line = line.strip()
+            if len(line) == 0:
+                continue
+            raw = json.loads(line)
+            if 'journal' in raw:
+                sessions.append({ 'header': raw, 'entries': [] })
+                continue
+            if len(sessions) == 0:
+                raise ValueError('%s:%d: entry found before a journal header' % (path, lineno))
+            if decode_values:
+                raw['args'] = decode(raw['args'])
+                raw['kwargs'] = decode(raw['kwargs'])
+                if 'result' in raw:
+                    raw['result'] = decode(raw['result'])
+            sessions[-1]['entries'].append(raw)
+
+    return sessions
@@ -0,0 +1,170 @@
+from datetime import datetime, timezone
+import numpy as np
+from ._types import *
+
+
+BITLINES = [c for c in range(64) if (c % 32) < 16]
+""" Bitline (row) channels in ascending order """
+WORDLINES = [c for c in range(64) if (c % 32) >= 16]
+""" Wordline (column) channels in ascending order """
+
+
+def _crossing(chan: int) -> List[int]:
+    return WORDLINES if chan in BITLINES else BITLINES
+
+
+class Measurement(np.lib.mixins.NDArrayOperatorsMixin):
+    """
+    The result of an operation along with the parameters it was done with.
+    ``Measurement`` objects are returned instead of bare numpy arrays when
+    structured results are enabled with
+    :meth:`~pyarc2.Instrument.set_structured`. The measured values are
+    available as :attr:`data` and the parameters as :attr:`metadata`, which
+    always includes the operation name, a UTC timestamp, the port of the
+    instrument, the path of the firmware file it was loaded with
+    (``firmware_file``) and the libarc2 version.
+
+    A ``Measurement`` exposes the ``shape``, ``dtype`` and ``ndim`` of its
+    data, supports indexing, iteration and the arithmetic and comparison
+    operators, and can be passed to numpy functions and ufuncs. The results
+    of these are plain numpy arrays, as the metadata no longer describes
+    them; use :attr:`data` (or ``np.asarray``) where an actual
+    ``numpy.ndarray`` instance is required.
+
+    >>> arc.set_structured(True)
+    >>> m = arc.read_all(0.2, BiasOrder.Rows)
+    >>> m.metadata['vread']
+    0.2
+    >>> df = m.to_pandas()
+
+    :param data: The measured values
+    :param dict metadata: The parameters of the measurement
+    :param times: The time of every value in seconds for traces
+    """
+
+    def __init__(self, data: Any, metadata: Optional[dict] = None, times: Any = None):
+        self.data = np.asarray(data)
+        """ The measured values as a numpy array """
+        self.metadata = dict(metadata or {})
+        """ The parameters of the measurement """
+        self.times = None if times is None else np.asarray(times)
+        """ The time of every value in seconds or ``None`` if not a trace """
+
+    def __array__(self, dtype=None, copy=None):
+        if dtype is None and not copy:
+            return self.data
+        return self.data.astype(self.data.dtype if dtype is None else dtype,
+            copy=bool(copy))
+
+    def __array_ufunc__(self, ufunc, method, *inputs, **kwargs):
+        inputs = tuple(x.data if isinstance(x, Measurement) else x for x in inputs)
+        if 'out' in kwargs:
+            kwargs['out'] = tuple(x.data if isinstance(x, Measurement) else x \
+                for x in kwargs['out'])
+        return getattr(ufunc, method)(*inputs, **kwargs)
+
+    @property
+    def shape(self) -> tuple:
+        """ The shape of the measured values """
+        return self.data.shape
+
+    @property
+    def dtype(self) -> np.dtype:
+        """ The data type of the measured values """
+        return self.data.dtype
+
+    @property
+    def ndim(self) -> int:
+        """ Number of dimensions of the measured values """
+        return self.data.ndim
+
+    def __len__(self):
+        return len(self.data)
+
+    def __iter__(self):
+        return iter(self.data)
+
+    def __getitem__(self, idx):
+        return self.data[idx]
+
+    def __repr__(self):
+        return 'Measurement<%s, shape=%s>' % \
+            (self.metadata.get('operation', 'unknown'), self.data.shape)
+
+    @property
+    def operation(self) -> Optional[str]:
+        """ The name of the operation that produced this measurement """
+        return self.metadata.get('operation')
+
+    def to_numpy(self) -> np.ndarray:
+        """
+        The measured values as a numpy array.
+        """
+        return self.data
+
+    def to_dict(self) -> dict:
+        """
+        Convert this measurement into a dict with two keys, ``data`` with the
+        measured values as (nested) lists and ``metadata``. Traces also
+        include ``times``.
+        """
+        res = { 'data': self.data.tolist(), 'metadata': dict(self.metadata) }
+        if self.times is not None:
+            res['times'] = self.times.tolist()
+        return res
+
+    def _dims(self) -> List[tuple[str, List[int]]]:
+        if self.data.ndim == 2 and self.data.shape == (32, 32):
+            return [('row', self.metadata.get('rows', BITLINES)),
+                    ('col', self.metadata.get('cols', WORDLINES))]
+        if self.data.ndim == 1 and self.times is not None:
+            return [('time', self.times.tolist())]
+        if self.data.ndim == 1 and 'channels' in self.metadata:
+            return [('channel', self.metadata['channels'])]
+        return [('dim_%d' % i, list(range(n))) for (i, n) in enumerate(self.data.shape)]
+
+    def to_pandas(self) -> Any:
+        """
+        Convert this measurement into a pandas ``Series`` (1D data) or
+        ``DataFrame`` (2D data) indexed by channel. Metadata is stored in
+        the ``attrs`` of the result. Requires pandas.
+
+        :raises ImportError: If pandas is not installed
+        """
+        import pandas as pd
+
+        dims = self._dims()
+        if self.data.ndim == 2:
+            res = pd.DataFrame(self.data, index=pd.Index(dims[0][1], name=dims[0][0]),
+                columns=pd.Index(dims[1][1], name=dims[1][0]))
+        elif self.data.ndim == 1:
+            res = pd.Series(self.data, index=pd.Index(dims[0][1], name=dims[0][0]))
+        else:
+            res = pd.Series([self.data.item()])
+        res.attrs.update(self.metadata)
+        return res
+
+    def to_xarray(self) -> Any:
+        """
+        Convert this measurement into an xarray ``DataArray`` with channel
+        coordinates. Metadata is stored in the ``attrs`` of the result.
+        Requires xarray.
+
+        :raises ImportError: If xarray is not installed
+        """
+        import xarray as xr
+
+        dims = self._dims()
+        return xr.DataArray(self.data, dims=[d[0] for d in dims],
+            coords={ d[0]: d[1] for d in dims }, attrs=dict(self.metadata))
+
+
+def _metadata(instrument: Any, operation: str, **params) -> dict:
+    from . import pyarc2 as _native
+    meta = { 'operation': operation }
+    meta.update(params)
+    meta['timestamp'] = datetime.now(timezone.utc).isoformat()
+    meta['port'] = instrument.port()
+    meta['firmware_file'] = instrument.firmware()
+    meta['libarc2'] = getattr(_native, 'LIBARC2_VERSION', None)
+    return meta
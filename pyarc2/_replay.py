@@ -0,0 +1,133 @@
+import builtins
+from functools import partial
+import json
+from . import pyarc2 as _native
+from ._types import *
+from ._journal import encode, decode, read_journal
+
+
+class ReplayError(Exception):
+    """
+    Raised by :class:`~pyarc2.ReplayInstrument` when a call does not match
+    the recorded session or the session has been exhausted.
+    """
+    pass
+
+
+def _recorded_error(error: dict) -> Exception:
+    name = error['type']
+    message = error['message']
+    for module in (_native, builtins):
+        cls = getattr(module, name, None)
+        if isinstance(cls, type) and issubclass(cls, Exception):
+            return cls(message)
+    return ReplayError('%s: %s' % (name, message))
+
+
+def _same_encoding(value: Any, recorded: Any, instrument: Any) -> bool:
+    # compare serialised forms so that values that went through the JSON
+    # round trip, such as tuples or NaNs, compare equal to the live ones
+    return json.dumps(encode(value, instrument), sort_keys=True) == \
+        json.dumps(recorded, sort_keys=True)
+
+
+class ReplayInstrument:
+    """
+    A stand-in for :class:`~pyarc2.Instrument` that replays a session
+    recorded with :meth:`~pyarc2.Instrument.start_journal`. Every method
+    call is matched against the next recorded command and returns the
+    recorded result, or raises the recorded error, without any hardware.
+    Chainable methods return the stand-in itself. This gives deterministic
+    reproduction of analysis code run against real lab sessions and can be
+    used to build regression tests from them.
+
+    By default the arguments of every call must match the recorded ones;
+    with ``strict=False`` only the method name is checked.
+
+    >>> arc = ReplayInstrument('experiment.jsonl')
+    >>> run_analysis(arc)  # the same code that ran on the instrument
+    >>> assert arc.remaining() == 0
+
+    :param str path: The journal file
+    :param int session: The session to replay if the journal contains
+                        several; defaults to the last one
+    :param bool strict: Whether arguments must match the recording
+    :raises ReplayError: If the journal contains no sessions
+    """
+
+    def __init__(self, path: str, session: int = -1, strict: bool = True):
+        sessions = read_journal(path, decode_values=False)
+        if len(sessions) == 0:
+            raise ReplayError('No recorded sessions in %s' % path)
+        self._header = sessions[session]['header']
+        self._entries = sessions[session]['entries']
+        self._pos = 0
+        self._strict = strict
+
+    def __getattr__(self, name: str) -> Callable:
+        if name.startswith('_'):
+            raise AttributeError(name)
+        return partial(self._replay, name)
+
+    def _replay(self, name: str, *args, **kwargs) -> Any:
+        if self._pos >= len(self._entries):
+            raise ReplayError('Call to %s after the end of the recorded session' % name)
+
+        entry = self._entries[self._pos]
+
+        if entry['command'] != name:
+            raise ReplayError('Call #%d: expected %s, got %s' % \
+                (entry['seq'], entry['command'], name))
+
+        if self._strict and (not _same_encoding(list(args), entry['args'], self) or \
+            not _same_encoding(kwargs, entry['kwargs'], self)):
+            raise ReplayError('Call #%d (%s): arguments differ from the recording' % \
+                (entry['seq'], name))
+
+        self._pos += 1
+
+        if 'error' in entry:
+            raise _recorded_error(entry['error'])
+
+        result = entry['result']
+        if isinstance(result, dict) and result.get('__self__', False):
+            return self
+        return decode(result)
+
+    def get_iter(self, mode: Any, rtype: Any = None):
+        """
+        Same as :meth:`pyarc2.Instrument.get_iter`; the iterator is fed by
+        the recorded :meth:`~pyarc2.Instrument.pick_one` calls.
+        """
+        self._replay('get_iter', *([mode] if rtype is None else [mode, rtype]))
+        if rtype is None:
+            rtype = _native.ReadType.Current
+
+        def _next():
+            data = self.pick_one(mode, rtype)
+            if data is None:
+                return None
+            return [data]
+
+        return iter(_next, None)
+
+    def header(self) -> dict:
+        """
+        The header of the replayed session, which identifies the instrument
+        and firmware it was recorded with.
+        """
+        return dict(self._header)
+
+    def port(self) -> int:
+        """ The port of the recorded instrument """
+        return self._header.get('port')
+
+    def firmware(self) -> str:
+        """ The path of the firmware file of the recorded instrument """
+        return self._header.get('firmware')
+
+    def remaining(self) -> int:
+        """
+        Number of recorded calls that have not been replayed yet.
+        """
+        return len(self._entries) - self._pos
@@ -1,10 +1,13 @@
 from ._types import *
 from . import pyarc2
 import numpy as np
+from ._measurement import Measurement
 
 
 class ArC2Error(Exception): ...
 
+class SafetyLimitError(Exception): ...
+
 class BiasOrder:
     Cols: ClassVar[pyarc2.BiasOrder] = ...
     Rows: ClassVar[pyarc2.BiasOrder] = ...
@@ -56,14 +59,156 @@ class OutputRange:
     @classmethod
     def __init__(cls) -> None: ...
 
+class WeightMap:
+    def __init__(self, inputs: Iterable[int], outputs: Iterable[int], g_unit: float = 1.0) -> None: ...
+    @staticmethod
+    def differential(inputs: Iterable[int], pairs: Iterable[tuple[int, int]],
+        g_unit: float = 1.0) -> WeightMap: ...
+    @property
+    def inputs(self) -> List[int]: ...
+    @property
+    def outputs(self) -> List[tuple[int, Optional[int]]]: ...
+    @property
+    def g_unit(self) -> float: ...
+    @property
+    def differential_pairs(self) -> bool: ...
+
+class MACEncoding:
+    @staticmethod
+    def BitSerial(bits: int) -> MACEncoding: ...
+    @staticmethod
+    def PulseCount(max_count: int) -> MACEncoding: ...
+
+class SafetyLimits:
+    max_voltage: Optional[float]
+    max_pulse_width: Optional[int]
+    max_pulses: Optional[int]
+    max_dose: Optional[float]
+    soft_voltage: Optional[float]
+    def __init__(self, max_voltage: Optional[float] = None, max_pulse_width: Optional[int] = None,
+        max_pulses: Optional[int] = None, max_dose: Optional[float] = None,
+        soft_voltage: Optional[float] = None) -> None: ...
+    def set_channel_voltage(self, chan: int, voltage: Optional[float]) -> None: ...
+    def channel_voltage(self, chan: int) -> Optional[float]: ...
+
+class ChannelMode:
+    Arbitrary: ClassVar[pyarc2.ChannelMode] = ...
+    HighSpeed: ClassVar[pyarc2.ChannelMode] = ...
+    Open: ClassVar[pyarc2.ChannelMode] = ...
+    HardGnd: ClassVar[pyarc2.ChannelMode] = ...
+    AcGnd: ClassVar[pyarc2.ChannelMode] = ...
+    CurrentSource: ClassVar[pyarc2.ChannelMode] = ...
+    @classmethod
+    def __init__(cls) -> None: ...
+
+class Calibration:
+    def __init__(self, offsets: Optional[List[float]] = None,
+        gains: Optional[List[float]] = None) -> None: ...
+    @property
+    def offsets(self) -> List[float]: ...
+    @property
+    def gains(self) -> List[float]: ...
+    def correct(self, chan: int, current: float) -> float: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(data: str) -> Calibration: ...
+    def save(self, path: str) -> None: ...
+    @staticmethod
+    def load(path: str) -> Calibration: ...
+
+class ChannelReport:
+    @property
+    def channel(self) -> int: ...
+    @property
+    def voltage_error(self) -> float: ...
+    @property
+    def leakage(self) -> float: ...
+    @property
+    def load_error(self) -> Optional[float]: ...
+    @property
+    def issues(self) -> List[str]: ...
+    @property
+    def passed(self) -> bool: ...
+
+class SelfTestReport:
+    @property
+    def channels(self) -> List[ChannelReport]: ...
+    @property
+    def passed(self) -> bool: ...
+    @property
+    def failures(self) -> List[int]: ...
+
+class CrosspointReport:
+    @property
+    def currents(self) -> np.ndarray: ...
+    @property
+    def shorts(self) -> List[tuple[int, int]]: ...
+    @property
+    def opens(self) -> List[tuple[int, int]]: ...
+    @property
+    def ok(self) -> int: ...
+
+class ArrowWriter:
+    def __init__(self, directory: str, experiment: str, metadata: Optional[dict] = None,
+        batch_rows: int = 1024, rows_per_file: int = 1000000) -> None: ...
+    def write(self, data: np.ndarray, operation: str, channels: Optional[List[int]] = None,
+        metadata: Optional[dict] = None) -> None: ...
+    def drain(self, instrument: InstrumentLL, mode: DataMode,
+        rtype: Optional[ReadType] = None) -> int: ...
+    def flush(self) -> None: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> ArrowWriter: ...
+    def __exit__(self, *args: Any) -> bool: ...
+    @property
+    def directory(self) -> str: ...
+    @property
+    def rows(self) -> int: ...
+
+class ChannelState:
+    @property
+    def channel(self) -> int: ...
+    @property
+    def mode(self) -> ChannelMode: ...
+    @property
+    def voltage(self) -> float: ...
+    @property
+    def applied_voltage(self) -> float: ...
+    @property
+    def range(self) -> OutputRange: ...
+    @property
+    def grounded(self) -> bool: ...
+    @property
+    def ac_grounded(self) -> bool: ...
+
+class Snapshot:
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(data: str) -> Snapshot: ...
+    @property
+    def selectors(self) -> Optional[List[int]]: ...
+    @property
+    def aux(self) -> dict[str, float]: ...
+
 class InstrumentLL:
     @classmethod
     def __init__(cls, port: int, fw: str, init: Optional[bool]) -> None: ...
     def bit_currents_from_address(self, addr: int) -> np.ndarray: ...
     def busy(self) -> bool: ...
     def config_channels(self, input: Iterable[tuple[int, float]], base: Optional[float]) -> InstrumentLL: ...
+    def config_channels_raw(self, input: Iterable[tuple[int, int]], base: Optional[int] = None) -> InstrumentLL: ...
+    def quantise(self, voltage: float, range: OutputRange) -> float: ...
+    def dac_code(self, voltage: float, range: OutputRange) -> int: ...
+    def dac_voltage(self, code: int, range: OutputRange) -> float: ...
+    def applied_voltages(self) -> dict[int, float]: ...
     def config_aux_channels(self, input: Iterable[tuple[AuxDACFn, float]]) -> InstrumentLL: ...
     def config_selectors(self, input: Iterable[int]) -> InstrumentLL: ...
+    def aux_channels(self) -> dict[str, float]: ...
+    def save_aux_preset(self, name: str,
+        voltages: Optional[Iterable[tuple[AuxDACFn, float]]] = None) -> None: ...
+    def apply_aux_preset(self, name: str) -> InstrumentLL: ...
+    def aux_preset(self, name: str) -> dict[str, float]: ...
+    def aux_presets(self) -> List[str]: ...
+    def remove_aux_preset(self, name: str) -> None: ...
     def connect_to_gnd(self, chans: IntIterable) -> InstrumentLL: ...
     def gnd_add(self, chans: IntIterable) -> InstrumentLL: ...
     def gnd_remove(self, chans: IntIterable) -> InstrumentLL: ...
@@ -71,6 +216,8 @@ class InstrumentLL:
     def gnd_ac_add(self, chans: IntIterable) -> InstrumentLL: ...
     def gnd_ac_remove(self, chans: IntIterable) -> InstrumentLL: ...
     def currents_from_address(self, addr: int, chans: IntIterable) -> np.ndarray: ...
+    def port(self) -> int: ...
+    def firmware(self) -> str: ...
     def delay(self, nanos: int) -> InstrumentLL: ...
     def execute(self) -> InstrumentLL: ...
     def float_all(self) -> InstrumentLL: ...
@@ -85,6 +232,11 @@ class InstrumentLL:
     def ground_all_fast(self) -> InstrumentLL: ...
     def open_channels(self, channels: Iterable[int]) -> InstrumentLL: ...
     def pick_one(self, mode: DataMode, rtype: ReadType) -> Optional[np.ndarray]: ...
+    def fast_pulse(self, channels: dict[int, tuple[float, int]], normal: float = 0.0,
+        preset_state: bool = True) -> InstrumentLL: ...
+    def run_table(self, lows: np.ndarray, highs: np.ndarray, voltages: np.ndarray,
+        widths: np.ndarray, reads: np.ndarray, vread: float,
+        chunk: Optional[int] = None) -> np.ndarray: ...
     def pulse_all(self, vpulse: float, nanos: int, order: BiasOrder) -> InstrumentLL: ...
     def pulse_one(self, low: int, high: int, voltage: float, nanos: int) -> InstrumentLL: ...
     def pulse_slice(self, chan: int, voltage: float, nanos: int) -> InstrumentLL: ...
@@ -97,21 +249,73 @@ class InstrumentLL:
     def pulseread_slice_masked(self, chan: int, mask: IntIterable, vpulse: float,
         nanos: int, vread: float) -> np.ndarray: ...
     def mac(self, inp_chans: List[tuple[int, float]], out_chans: Iterable[int]) -> np.ndarray: ...
-    def read_all(self, vread: float, order: BiasOrder) -> np.ndarray: ...
-    def read_one(self, low: int, high: int, vread: float) -> float: ...
-    def read_slice(self, chan: int, vread: float) -> np.ndarray: ...
+    def mac_encoded(self, inp_chans: List[tuple[int, int]], out_chans: Iterable[int],
+        vread: float, encoding: MACEncoding) -> np.ndarray: ...
+    def mvm(self, inputs: np.ndarray, weights_map: WeightMap, v_scale: float,
+        vmax: Optional[float] = None) -> np.ndarray: ...
+    def read_all(self, vread: float, order: BiasOrder, baseline: bool = False) -> np.ndarray: ...
+    def set_gate_map(self, mapping: dict[int, int]) -> None: ...
+    def gate_map(self) -> dict[int, int]: ...
+    def enable_gating(self, voltage: float) -> InstrumentLL: ...
+    def disable_gating(self) -> InstrumentLL: ...
+    def gate_voltage(self) -> Optional[float]: ...
+    def gate_ramp(self, low: int, high: int, vread: float, vstart: float, vstep: float,
+        vstop: float, vpulse: Optional[float] = None, nanos: Optional[int] = None) -> np.ndarray: ...
+    def read_one(self, low: int, high: int, vread: float, baseline: bool = False) -> float: ...
+    def read_slice(self, chan: int, vread: float, baseline: bool = False) -> np.ndarray: ...
     def read_slice_masked(self, chan: int, mask: IntIterable, vread: float) -> np.ndarray: ...
     def read_slice_open(self, highs: IntIterable, ground_after: bool) -> np.ndarray: ...
+    def read_one_deferred(self, low: int, high: int, vread: float) -> InstrumentLL: ...
     def read_slice_open_deferred(self, highs: IntIterable, ground_after: bool) -> InstrumentLL: ...
+    def configure_current_source(self, cref: float, resistor: float) -> None: ...
+    def force_current(self, chan: int, amps: float, averaging: bool = True) -> float: ...
+    def current_ramp(self, chan: int, istart: float, istep: float, istop: float,
+        inter_nanos: int = 0, averaging: bool = True) -> np.ndarray: ...
+    def four_wire(self, force_high: int, force_low: int, sense_high: int, sense_low: int,
+        voltage: Optional[float] = None, current: Optional[float] = None, nreads: int = 1,
+        averaging: bool = True) -> tuple[float, float, float]: ...
     def vread_channels(self, chans: IntIterable, averaging: bool) -> List[float]: ...
     def vread_channels_deferred(self, chans: IntIterable, averaging: bool) -> InstrumentLL: ...
     def read_train(self, low: int, high: int, vread: float, interpulse: int,
-        preload: Optional[float], condition: WaitFor) -> None: ...
+        preload: Optional[float], condition: WaitFor, baseline: bool = False) -> Optional[int]: ...
     def set_control_mode(self, mode: ControlMode) -> InstrumentLL: ...
     def set_logic(self, mask: int, cl0: Optional[pyarc2.IODir], cl1: Optional[pyarc2.IODir], cl2: Optional[pyarc2.IODir], cl3: Optional[pyarc2.IODir]) -> InstrumentLL: ...
     def set_logic_level(self, level: LogicLevel) -> InstrumentLL: ...
+    def define_pin(self, name: str, pin: int, direction: pyarc2.IODir) -> None: ...
+    def pins(self) -> dict[str, tuple[int, pyarc2.IODir]]: ...
+    def set_pins(self, states: dict[str, bool]) -> InstrumentLL: ...
+    def set_gpio(self, pins: Iterable[int], state: bool) -> InstrumentLL: ...
+    def pulse_pin(self, name: str, nanos: int, active_high: bool = True) -> InstrumentLL: ...
+    def get_pins(self, names: Optional[Iterable[str]] = None) -> dict[str, bool]: ...
+    def trigger_out(self, pin: int, edge: Optional[TriggerEdge] = None) -> InstrumentLL: ...
+    def trigger_end(self, pin: int, edge: Optional[TriggerEdge] = None) -> InstrumentLL: ...
+    def wait_for(self, condition: WaitFor, timeout: Optional[int] = None) -> InstrumentLL: ...
+    def declare_peripheral_level(self, level: Optional[LogicLevel]) -> None: ...
+    def set_auto_range(self, enabled: bool) -> None: ...
+    def auto_range(self) -> bool: ...
+    def range_switches(self, reset: bool = False) -> List[List[int]]: ...
     def set_channel_range(self, chans: IntIterable, rng: OutputRange) -> InstrumentLL: ...
     def wait(self) -> None: ...
+    def set_calibration(self, calibration: Optional[Calibration]) -> None: ...
+    def calibration(self) -> Optional[Calibration]: ...
+    def set_correction(self, enabled: bool) -> None: ...
+    def correction(self) -> bool: ...
+    def measure_calibration(self, loads: Optional[dict[int, tuple[int, float]]] = None,
+        vread: float = 0.2, nreads: int = 16) -> Calibration: ...
+    def self_test(self, vtest: float = 1.0, vtol: float = 0.05, itol: float = 1e-8,
+        loads: Optional[dict[int, tuple[int, float]]] = None, vread: float = 0.2,
+        rtol: float = 0.05) -> SelfTestReport: ...
+    def scan_shorts_opens(self, threshold_low: float, threshold_high: float, vread: float = 0.2,
+        order: Optional[BiasOrder] = None) -> CrosspointReport: ...
+    def channel_state(self) -> List[ChannelState]: ...
+    def snapshot(self) -> Snapshot: ...
+    def restore(self, snapshot: Snapshot) -> InstrumentLL: ...
+    def set_safety_limits(self, limits: SafetyLimits) -> None: ...
+    def safety_limits(self) -> SafetyLimits: ...
+    def arm(self, state: bool = True) -> InstrumentLL: ...
+    def armed(self) -> bool: ...
+    def device_exposure(self, low: int, high: int) -> tuple[int, float]: ...
+    def reset_exposure(self) -> None: ...
     def word_currents_from_address(self, addr: int) -> np.ndarray: ...
 
 class ReadAfter:
@@ -131,11 +335,26 @@ class ReadAt:
     def Arb(self, voltage) -> ReadAt: ...
     def voltage(self) -> float: ...
 
+class TriggerEdge:
+    Rising: ClassVar[pyarc2.TriggerEdge] = ...
+    Falling: ClassVar[pyarc2.TriggerEdge] = ...
+    @classmethod
+    def __init__(cls) -> None: ...
+
 class WaitFor:
     @classmethod
     def __init__(cls) -> None: ...
     def Iterations(self, iterations: int) -> WaitFor: ...
     def Millis(self, millis: int) -> WaitFor: ...
     def Nanos(self, nanos: int) -> WaitFor: ...
+    def Trigger(self, pin: int, edge: TriggerEdge) -> WaitFor: ...
+    def Current(self, threshold: float, above: bool,
+        max_reads: Optional[int] = None) -> WaitFor: ...
 
 def find_ids() -> List[int]: ...
+def save_map(path: str, array: Union[np.ndarray, Measurement],
+    metadata: Optional[dict] = None) -> None: ...
+def load_map(path: str) -> Measurement: ...
+def save_trace(path: str, times: Union[Iterable[float], Measurement],
+    currents: Optional[Iterable[float]] = None, metadata: Optional[dict] = None) -> None: ...
+def load_trace(path: str) -> Measurement: ...
@@ -5,11 +5,41 @@ use libarc2::{BiasOrder, ControlMode, DataMode, ReadAt, ReadAfter, ReadType, fin
 use libarc2::ArC2Error as LLArC2Error;
 use libarc2::registers::{IOMask, IODir, AuxDACFn, OutputRange};
 use std::borrow::Borrow;
-use std::convert::{From, Into, TryInto};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::{From, Into, TryFrom, TryInto};
 use pyo3::prelude::{pymodule, pyclass, pymethods};
-use pyo3::prelude::{PyAnyMethods, PyModule, PyModuleMethods, PyRefMut, PyResult, Python, PyErr, Bound};
+use pyo3::prelude::{PyAny, PyAnyMethods, PyModule, PyModuleMethods, PyRef, PyRefMut, PyResult, Python, PyErr, Bound};
 use pyo3::{intern, exceptions, create_exception};
-use numpy::{PyArray, PyReadonlyArray1, PyArrayMethods, IntoPyArray, Ix1, Ix2};
+use pyo3::types::{PyDict, PyDictMethods};
+use serde::{Serialize, Deserialize};
+use numpy::{PyArray, PyReadonlyArray1, PyReadonlyArray2, PyArrayMethods, IntoPyArray, Ix1, Ix2};
+use ndarray::Array2;
+use ndarray_npy::{NpzReader, NpzWriter};
+#[cfg(feature = "arrow")]
+use std::fs::File;
+#[cfg(feature = "arrow")]
+use std::io::BufWriter;
+#[cfg(feature = "arrow")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "arrow")]
+use std::sync::Arc;
+#[cfg(feature = "arrow")]
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "arrow")]
+use pyo3::types::PyTuple;
+#[cfg(feature = "arrow")]
+use numpy::{PyReadonlyArrayDyn, PyUntypedArrayMethods};
+#[cfg(feature = "arrow")]
+use arrow::array::{ArrayRef, Float32Builder, ListBuilder, StringBuilder, UInt32Builder,
+    UInt64Builder, TimestampMicrosecondBuilder};
+#[cfg(feature = "arrow")]
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+#[cfg(feature = "arrow")]
+use arrow::error::ArrowError;
+#[cfg(feature = "arrow")]
+use arrow::ipc::writer::StreamWriter;
+#[cfg(feature = "arrow")]
+use arrow::record_batch::RecordBatch;
 
 
 /// BiasOrder is used in combination with the multi-crosspoint pulse and
@@ -36,6 +66,13 @@ impl PyBiasOrder {
     fn Cols() -> PyBiasOrder {
         PyBiasOrder { _inner: BiasOrder::Columns }
     }
+
+    fn __str__(&self) -> &'static str {
+        match self._inner {
+            BiasOrder::Rows => "Rows",
+            BiasOrder::Columns => "Cols"
+        }
+    }
 }
 
 impl From<BiasOrder> for PyBiasOrder {
@@ -103,6 +140,22 @@ impl PyReadAt {
             _ => Err(exceptions::PyException::new_err("No voltage associated"))
         }
     }
+
+    fn __str__(&self) -> String {
+        match self._inner {
+            ReadAt::Bias => "ReadAt.Bias".to_string(),
+            ReadAt::Arb(v) => format!("ReadAt.Arb({:?})", v),
+            ReadAt::Never => "ReadAt.Never".to_string()
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        match self._inner {
+            ReadAt::Bias => "ReadAt<Bias>".to_string(),
+            ReadAt::Arb(v) => format!("ReadAt<Arb({:?})>", v),
+            ReadAt::Never => "ReadAt<Never>".to_string()
+        }
+    }
 }
 
 impl From<ReadAt> for PyReadAt {
@@ -241,6 +294,14 @@ impl PyControlMode {
     fn Internal() -> PyControlMode {
         PyControlMode { _inner: ControlMode::Internal }
     }
+
+    fn __str__(&self) -> String {
+        format!("ControlMode.{}", control_mode_name(&self._inner))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ControlMode<{}>", control_mode_name(&self._inner))
+    }
 }
 
 impl From<ControlMode> for PyControlMode {
@@ -286,6 +347,22 @@ impl PyDataMode {
     fn All() -> PyDataMode {
         PyDataMode { _inner: DataMode::All }
     }
+
+    fn __str__(&self) -> String {
+        format!("DataMode.{}", data_mode_name(&self._inner))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DataMode<{}>", data_mode_name(&self._inner))
+    }
+}
+
+fn data_mode_name(mode: &DataMode) -> &'static str {
+    match mode {
+        DataMode::Words => "Words",
+        DataMode::Bits => "Bits",
+        DataMode::All => "All"
+    }
 }
 
 impl From<DataMode> for PyDataMode {
@@ -317,6 +394,21 @@ impl PyReadType {
     fn Voltage() -> PyReadType {
         PyReadType { _inner: ReadType::Voltage }
     }
+
+    fn __str__(&self) -> String {
+        format!("ReadType.{}", read_type_name(&self._inner))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ReadType<{}>", read_type_name(&self._inner))
+    }
+}
+
+fn read_type_name(rtype: &ReadType) -> &'static str {
+    match rtype {
+        ReadType::Current => "Current",
+        ReadType::Voltage => "Voltage"
+    }
 }
 
 impl From<ReadType> for PyReadType {
@@ -331,11 +423,63 @@ impl From<PyReadType> for ReadType {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum TriggerEdge {
+    Rising,
+    Falling
+}
+
+/// Signal edge used with trigger operations such as
+/// :meth:`pyarc2.WaitFor.Trigger` and :meth:`pyarc2.Instrument.trigger_out`.
+///
+/// :var Rising: Low to high transition
+/// :var Falling: High to low transition
+#[pyclass(name="TriggerEdge", module="pyarc2")]
+#[derive(Clone)]
+struct PyTriggerEdge { _inner: TriggerEdge }
+
+#[allow(non_snake_case)]
+#[pymethods]
+impl PyTriggerEdge {
+
+    #[classattr]
+    fn Rising() -> PyTriggerEdge {
+        PyTriggerEdge { _inner: TriggerEdge::Rising }
+    }
+
+    #[classattr]
+    fn Falling() -> PyTriggerEdge {
+        PyTriggerEdge { _inner: TriggerEdge::Falling }
+    }
+
+    fn __str__(&self) -> &'static str {
+        match self._inner {
+            TriggerEdge::Rising => "TriggerEdge.Rising",
+            TriggerEdge::Falling => "TriggerEdge.Falling"
+        }
+    }
+
+    fn __repr__(&self) -> &'static str {
+        match self._inner {
+            TriggerEdge::Rising => "TriggerEdge<Rising>",
+            TriggerEdge::Falling => "TriggerEdge<Falling>"
+        }
+    }
+}
+
+#[derive(Clone)]
+enum WaitCondition {
+    Native(WaitFor),
+    Trigger(usize, TriggerEdge),
+    Current { threshold: f32, above: bool, max_reads: Option<usize> }
+}
+
 /// Wait condition for long running operations, such as
-/// :meth:`pyarc2.Instrument.read_train`.
+/// :meth:`pyarc2.Instrument.read_train`, or for pausing a sequence of
+/// operations with :meth:`pyarc2.Instrument.wait_for`.
 #[pyclass(name="WaitFor", module="pyarc2")]
 #[derive(Clone)]
-struct PyWaitFor { _inner: WaitFor }
+struct PyWaitFor { _inner: WaitCondition }
 
 #[allow(non_snake_case)]
 #[pymethods]
@@ -347,7 +491,7 @@ impl PyWaitFor {
     /// :return: A new ``WaitFor`` directive
     #[staticmethod]
     fn Nanos(nanos: u64) -> PyWaitFor {
-        PyWaitFor { _inner: WaitFor::Time(std::time::Duration::from_nanos(nanos)) }
+        WaitFor::Time(std::time::Duration::from_nanos(nanos)).into()
     }
 
     /// Wait a specified number of milliseconds
@@ -356,7 +500,7 @@ impl PyWaitFor {
     /// :return: A new ``WaitFor`` directive
     #[staticmethod]
     fn Millis(millis: u64) -> PyWaitFor {
-        PyWaitFor { _inner: WaitFor::Time(std::time::Duration::from_millis(millis)) }
+        WaitFor::Time(std::time::Duration::from_millis(millis)).into()
     }
 
     /// Wait a specified number of iterations
@@ -365,19 +509,90 @@ impl PyWaitFor {
     /// :return: A new ``WaitFor`` directive
     #[staticmethod]
     fn Iterations(iters: usize) -> PyWaitFor {
-        PyWaitFor { _inner: WaitFor::Iterations(iters) }
+        WaitFor::Iterations(iters).into()
+    }
+
+    /// Wait for an external signal edge on a GPIO. The GPIO must belong to
+    /// a cluster configured as input (see :class:`~pyarc2.IODir`). This
+    /// condition can only be used with :meth:`pyarc2.Instrument.wait_for`.
+    ///
+    /// :param int pin: The GPIO number (0–31)
+    /// :param edge: A variant of :class:`~pyarc2.TriggerEdge`
+    /// :return: A new ``WaitFor`` directive
+    /// :raises ValueError: If the GPIO is out of range
+    #[staticmethod]
+    fn Trigger(pin: usize, edge: PyTriggerEdge) -> PyResult<PyWaitFor> {
+        if pin >= 32 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("GPIO {} is out of range (0-31)", pin)));
+        }
+        Ok(PyWaitFor { _inner: WaitCondition::Trigger(pin, edge._inner) })
+    }
+
+    /// Wait until the measured current crosses a threshold. The absolute
+    /// value of the current is compared against ``threshold``. This
+    /// condition can only be used with :meth:`pyarc2.Instrument.read_train`.
+    ///
+    /// :param float threshold: The current threshold in A
+    /// :param bool above: ``True`` to stop once the current rises above the
+    ///                    threshold, ``False`` to stop once it falls below
+    /// :param int max_reads: Stop after this many reads even if the threshold
+    ///                       has not been crossed; ``None`` for no limit
+    /// :return: A new ``WaitFor`` directive
+    /// :raises ValueError: If the threshold is not a positive number or
+    ///                     ``max_reads`` is zero
+    #[staticmethod]
+    #[pyo3(signature = (threshold, above, max_reads=None))]
+    fn Current(threshold: f32, above: bool, max_reads: Option<usize>) -> PyResult<PyWaitFor> {
+        if threshold.is_nan() || threshold <= 0.0 {
+            return Err(exceptions::PyValueError::new_err(
+                "Current threshold must be a positive number"));
+        }
+        if max_reads == Some(0) {
+            return Err(exceptions::PyValueError::new_err("max_reads must be at least 1"));
+        }
+        Ok(PyWaitFor { _inner: WaitCondition::Current { threshold, above, max_reads } })
+    }
+
+    fn __str__(&self) -> String {
+        match &self._inner {
+            WaitCondition::Native(WaitFor::Time(duration)) =>
+                format!("WaitFor.Nanos({})", duration.as_nanos()),
+            WaitCondition::Native(WaitFor::Iterations(iters)) =>
+                format!("WaitFor.Iterations({})", iters),
+            WaitCondition::Trigger(pin, edge) =>
+                format!("WaitFor.Trigger({}, {})", pin,
+                    PyTriggerEdge { _inner: *edge }.__str__()),
+            WaitCondition::Current { threshold, above, max_reads } =>
+                format!("WaitFor.Current({:?}, {}, {})", threshold,
+                    if *above { "True" } else { "False" },
+                    match max_reads {
+                        Some(n) => n.to_string(),
+                        None => "None".to_string()
+                    })
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("WaitFor<{}>", &self.__str__()["WaitFor.".len()..])
     }
 }
 
 impl From<WaitFor> for PyWaitFor {
     fn from(waitfor: WaitFor) -> Self {
-        PyWaitFor { _inner: waitfor }
+        PyWaitFor { _inner: WaitCondition::Native(waitfor) }
     }
 }
 
-impl From<PyWaitFor> for WaitFor {
-    fn from(waitfor: PyWaitFor) -> Self {
-        waitfor._inner
+impl TryFrom<PyWaitFor> for WaitFor {
+    type Error = PyErr;
+
+    fn try_from(waitfor: PyWaitFor) -> Result<Self, Self::Error> {
+        match waitfor._inner {
+            WaitCondition::Native(w) => Ok(w),
+            _ => Err(exceptions::PyValueError::new_err(
+                "This wait condition is not supported by this operation"))
+        }
     }
 }
 
@@ -467,6 +682,10 @@ impl PyAuxDACFn {
     fn LGC() -> PyAuxDACFn {
         PyAuxDACFn { _inner: AuxDACFn::LGC }
     }
+
+    fn __str__(&self) -> String {
+        format!("AuxDACFn.{}", AUX_NAMES[aux_index(self._inner)])
+    }
 }
 
 impl From<AuxDACFn> for PyAuxDACFn {
@@ -571,6 +790,14 @@ impl PyLogicLevel {
         PyLogicLevel { _inner:LogicLevel::LL5V }
     }
 
+    fn __str__(&self) -> String {
+        format!("LogicLevel.{}", logic_level_name(&self._inner))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("LogicLevel<{}>", logic_level_name(&self._inner))
+    }
+
 }
 
 impl From<LogicLevel> for PyLogicLevel {
@@ -636,170 +863,2341 @@ impl From<&PyOutputRange> for OutputRange {
     }
 }
 
-/// Catch-all exception for low-level ArC2 errors
-/// --
-#[pyclass(name="ArC2Error", module="pyarc2")]
-struct PyArC2Error { _inner: LLArC2Error }
-
-#[pymethods]
-impl PyArC2Error {
+/// Maximum absolute voltage that :meth:`pyarc2.Instrument.mvm` will apply
+/// to an input channel unless told otherwise. This is kept well below the
+/// typical switching threshold of most devices so that a matrix-vector
+/// multiplication does not disturb the stored weights.
+const MVM_DEFAULT_VMAX: f32 = 0.5;
+
+/// Longest pulse that can be generated by the high speed drivers
+const FAST_PULSE_MAX_NANOS: u128 = 500_000_000;
+
+/// Size of a single read-out in the output memory of ArC TWO; every
+/// read-out stores all 64 channels as 4-byte values.
+const READOUT_BYTES: usize = 64 * 4;
+
+/// Output memory reserved for the read-outs of a single command buffer.
+/// Operations queueing more read-outs than fit in it are split into several
+/// command buffers, or rejected if they must run in one. libarc2 does not
+/// export the size of the output memory; this is a conservative figure that
+/// has not been checked against the firmware and must be replaced by the
+/// libarc2 constant once one is available.
+const OUTPUT_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Number of read-outs that fit in the output buffer of a single command
+/// buffer
+const BUFFER_READS: usize = OUTPUT_BUFFER_BYTES / READOUT_BYTES;
+
+/// Interval between successive GPIO reads while waiting for a trigger.
+/// The GIL is released in between so that other Python threads can run.
+const TRIGGER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_micros(200);
+
+/// Fraction of a step by which a ramp may fall short of its last value
+/// and still include it; see ``ramp_steps``.
+const RAMP_STEP_TOLERANCE: f64 = 1e-4;
+
+/// Number of reads queued at a time when a read train is terminated by
+/// a current threshold.
+const TRAIN_CHUNK_READS: usize = 64;
+
+/// Maximum allowed difference between ``CSET`` and ``CREF``. This is the
+/// operating limit of the ArC TWO current source given in the description
+/// of ``AuxDACFn::CREF`` and ``AuxDACFn::CSET`` ("must be within 1.5 V").
+const CSRC_MAX_DELTA: f32 = 1.5;
+
+/// Maximum output voltage in the standard (``STD``) range
+const STD_RANGE_MAX: f32 = 10.0;
+/// Maximum output voltage in the extended (``EXT``) range
+const EXT_RANGE_MAX: f32 = 20.0;
+
+/// Code of the 16-bit offset binary channel DACs that produces 0.0 V
+const DAC_ZERO_CODE: u16 = 0x8000;
+
+/// WeightMap describes how a logical weight matrix is laid out on the
+/// ArC TWO channels when used with :meth:`pyarc2.Instrument.mvm`. Each
+/// element of the input vector is driven on the corresponding ``inputs``
+/// channel and each element of the output vector is read from the
+/// corresponding ``outputs`` channel. For signed weights use
+/// :meth:`~pyarc2.WeightMap.differential` instead, where every output is
+/// the difference between a positive and a negative column.
+///
+/// ``g_unit`` is the conductance (in S) that corresponds to a weight of
+/// 1.0 and is used to scale currents back to the numeric domain.
+///
+/// >>> # 4 inputs on channels 0-3, 2 outputs on channels 16 and 17
+/// >>> wmap = WeightMap([0, 1, 2, 3], [16, 17], 1e-4)
+///
+/// :param inputs: The channels that input values are applied to
+/// :param outputs: The channels that output currents are read from
+/// :param float g_unit: Conductance corresponding to a unit weight
+/// :raises ValueError: If channels are out of range, used more than once or
+///                     shared between inputs and outputs or if ``g_unit``
+///                     is not positive
+#[pyclass(name="WeightMap", module="pyarc2")]
+#[derive(Clone)]
+struct PyWeightMap {
+    inputs: Vec<usize>,
+    outputs: Vec<(usize, Option<usize>)>,
+    g_unit: f32
+}
 
-    fn __str__(&self) -> String {
-        let inner = &self._inner;
-        format!("{}", inner)
-    }
+impl PyWeightMap {
 
-}
+    fn validate(inputs: Vec<usize>, outputs: Vec<(usize, Option<usize>)>, g_unit: f32)
+        -> PyResult<Self> {
 
-impl From<LLArC2Error> for PyArC2Error {
-    fn from(err: LLArC2Error) -> Self {
-        PyArC2Error { _inner: err }
-    }
-}
+        if inputs.is_empty() || outputs.is_empty() {
+            return Err(exceptions::PyValueError::new_err(
+                "WeightMap needs at least one input and one output channel"));
+        }
 
-impl From<PyArC2Error> for LLArC2Error {
-    fn from(err: PyArC2Error) -> Self {
-        err._inner
-    }
-}
+        if g_unit.is_nan() || g_unit <= 0.0 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("g_unit must be positive, got {}", g_unit)));
+        }
 
-create_exception!(pyarc2, ArC2Error, exceptions::PyException,
-    "Catch-all exception for low-level ArC2 errors. \
-    There are five broad categories of low-level errors: \
-    (1) FPGA communication errors, (2) Memory access errors \
-    (3) Invalid device ID, (4) Inconsistent ramp errors \
-    and (5) Output buffer access errors");
+        let mut outchans: Vec<usize> = Vec::with_capacity(2*outputs.len());
+        for (pos, neg) in &outputs {
+            if Some(*pos) == *neg {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Differential pair ({}, {}) uses the same channel twice", pos, pos)));
+            }
+            outchans.push(*pos);
+            if let Some(n) = neg {
+                outchans.push(*n);
+            }
+        }
 
-impl ArC2Error {
-    pub fn new_exception(err: LLArC2Error) -> PyErr {
-        ArC2Error::new_err(PyArC2Error { _inner: err })
-    }
-}
+        check_channels(inputs.iter().chain(outchans.iter()))?;
 
-#[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
-#[pyclass(name="InstrumentLL", module="pyarc2", subclass)]
-pub struct PyInstrument {
-    _instrument: Instrument
-}
+        for (idx, chan) in inputs.iter().enumerate() {
+            if inputs[..idx].contains(chan) {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Channel {} is used more than once as an input", chan)));
+            }
+            if outchans.contains(chan) {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Channel {} is used both as an input and an output", chan)));
+            }
+        }
 
-#[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
-impl PyInstrument {
+        for (idx, chan) in outchans.iter().enumerate() {
+            if outchans[..idx].contains(chan) {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Channel {} is used by more than one output", chan)));
+            }
+        }
 
-    /// Returns a reference to the underlying Instrument
-    pub fn inner(&self) -> &Instrument {
-        &self._instrument
+        Ok(PyWeightMap { inputs, outputs, g_unit })
     }
 
-    /// Returns a mutable reference to the underlying Instrument
-    pub fn inner_mut(&mut self) -> &mut Instrument {
-        &mut self._instrument
+    /// All channels that need to be read to evaluate the outputs
+    /// in ascending order with duplicates removed.
+    fn read_channels(&self) -> Vec<usize> {
+        let mut chans: Vec<usize> = Vec::with_capacity(2*self.outputs.len());
+        for (pos, neg) in &self.outputs {
+            chans.push(*pos);
+            if let Some(n) = neg {
+                chans.push(*n);
+            }
+        }
+        chans.sort_unstable();
+        chans.dedup();
+        chans
     }
 }
 
-#[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
 #[pymethods]
-impl PyInstrument {
+impl PyWeightMap {
 
     #[new]
-    #[pyo3(signature = (id, fw, init=None))]
-    fn new(id: i32, fw: &str, init: Option<bool>) -> PyResult<Self> {
-        let actual_init = match init {
-            Some(x) => x,
-            None => true
-        };
-        match Instrument::open_with_fw(id, fw, true, actual_init) {
-            Ok(instr) => Ok(PyInstrument { _instrument: instr }),
-            Err(err) => Err(ArC2Error::new_exception(err))
-        }
+    #[pyo3(signature = (inputs, outputs, g_unit=1.0))]
+    fn new(inputs: Vec<usize>, outputs: Vec<usize>, g_unit: f32) -> PyResult<Self> {
+        let outputs = outputs.into_iter().map(|c| (c, None)).collect();
+        PyWeightMap::validate(inputs, outputs, g_unit)
     }
 
-    /// delay(self, nanos, /)
+    /// differential(inputs, pairs, g_unit=1.0, /)
     /// --
     ///
-    /// Insert a delay of ``nanos`` nanoseconds in the command buffer.
-    fn delay<'py>(mut slf: PyRefMut<'py, Self>, nanos: u128) -> PyResult<PyRefMut<'py, Self>> {
-        match slf._instrument.add_delay(nanos) {
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
-        }
+    /// Create a new ``WeightMap`` for signed weights. Every output is read
+    /// from a pair of columns ``(positive, negative)`` and the output value
+    /// is proportional to the difference of the two currents.
+    ///
+    /// :param inputs: The channels that input values are applied to
+    /// :param pairs: A list of ``(positive channel, negative channel)`` tuples
+    /// :param float g_unit: Conductance corresponding to a unit weight
+    /// :return: A new ``WeightMap``
+    /// :raises ValueError: If channels are out of range, a pair uses the same
+    ///                     channel twice or a channel is used more than once
+    #[staticmethod]
+    #[pyo3(signature = (inputs, pairs, g_unit=1.0))]
+    fn differential(inputs: Vec<usize>, pairs: Vec<(usize, usize)>, g_unit: f32) -> PyResult<Self> {
+        let outputs = pairs.into_iter().map(|(p, n)| (p, Some(n))).collect();
+        PyWeightMap::validate(inputs, outputs, g_unit)
     }
 
-    /// ground_all(self, /)
-    /// --
-    ///
-    /// Ground all channels and revert them to arbitrary voltage operation.
-    fn ground_all<'py>(mut slf: PyRefMut<'py, Self>) -> PyResult<PyRefMut<'py, Self>> {
-        match slf._instrument.ground_all() {
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
-        }
+    /// The input channels of this map
+    #[getter]
+    fn inputs(&self) -> Vec<usize> {
+        self.inputs.clone()
     }
 
-    /// ground_all_fast(self, /)
+    /// The output channels of this map as ``(positive, negative)`` tuples;
+    /// ``negative`` is ``None`` for single-ended outputs.
+    #[getter]
+    fn outputs(&self) -> Vec<(usize, Option<usize>)> {
+        self.outputs.clone()
+    }
+
+    /// Conductance corresponding to a unit weight
+    #[getter]
+    fn g_unit(&self) -> f32 {
+        self.g_unit
+    }
+
+    /// Whether this map uses differential column pairs
+    #[getter]
+    fn differential_pairs(&self) -> bool {
+        self.outputs.iter().any(|(_, neg)| neg.is_some())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MACEncoding {
+    BitSerial(u32),
+    PulseCount(u32)
+}
+
+/// Input encoding for multi-bit inputs used with
+/// :meth:`pyarc2.Instrument.mac_encoded`. Instead of mapping each input to an
+/// analogue voltage, which is subject to device nonlinearity, inputs are
+/// applied as a series of identical read pulses.
+///
+/// With :meth:`~pyarc2.MACEncoding.BitSerial` every bit plane of the inputs
+/// is applied separately (least significant bit first) and the output
+/// currents are accumulated with binary weights. With
+/// :meth:`~pyarc2.MACEncoding.PulseCount` an input of value ``n`` receives
+/// ``n`` unit read pulses and the output currents are summed.
+#[pyclass(name="MACEncoding", module="pyarc2")]
+#[derive(Clone)]
+struct PyMACEncoding { _inner: MACEncoding }
+
+#[allow(non_snake_case)]
+#[pymethods]
+impl PyMACEncoding {
+
+    /// BitSerial(bits, /)
     /// --
     ///
-    /// Ground all channels maintaing current channel operating mode.
-    fn ground_all_fast<'py>(mut slf: PyRefMut<'py, Self>) -> PyResult<PyRefMut<'py, Self>> {
-        match slf._instrument.ground_all_fast() {
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
+    /// Apply inputs one bit plane at a time.
+    ///
+    /// :param int bits: The number of bits of every input (1 to 32)
+    /// :return: A new ``MACEncoding`` directive
+    /// :raises ValueError: If ``bits`` is out of range
+    #[staticmethod]
+    fn BitSerial(bits: u32) -> PyResult<PyMACEncoding> {
+        if bits == 0 || bits > 32 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Bit-serial encoding supports 1 to 32 bits, got {}", bits)));
         }
+        Ok(PyMACEncoding { _inner: MACEncoding::BitSerial(bits) })
     }
 
-    /// connect_to_gnd(self, chans, /)
+    /// PulseCount(max_count, /)
     /// --
     ///
-    /// Modify previously configured channels by switching them to ground. Use
-    /// an empty array to clear.
+    /// Apply inputs as a number of unit read pulses.
     ///
-    /// :param chans: The channels to ground; this must be a numpy uint64 array or
-    ///               any Iterable whose elements can be converted to uint64.
-    fn connect_to_gnd<'py>(mut slf: PyRefMut<'py, Self>, chans: PyReadonlyArray1<'py, usize>)
-        -> PyResult<PyRefMut<'py, Self>> {
+    /// :param int max_count: The largest input value, which is also the
+    ///                       number of read steps performed; at most 256
+    ///                       can be run by
+    ///                       :meth:`~pyarc2.Instrument.mac_encoded`
+    /// :return: A new ``MACEncoding`` directive
+    /// :raises ValueError: If ``max_count`` is 0
+    #[staticmethod]
+    fn PulseCount(max_count: u32) -> PyResult<PyMACEncoding> {
+        if max_count == 0 {
+            return Err(exceptions::PyValueError::new_err(
+                "Pulse-count encoding needs a maximum count of at least 1"));
+        }
+        Ok(PyMACEncoding { _inner: MACEncoding::PulseCount(max_count) })
+    }
 
-        let slice = chans.as_slice().unwrap();
-        match slf._instrument.connect_to_gnd(slice) {
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
+    fn __repr__(&self) -> String {
+        match self._inner {
+            MACEncoding::BitSerial(bits) => format!("MACEncoding<BitSerial({})>", bits),
+            MACEncoding::PulseCount(count) => format!("MACEncoding<PulseCount({})>", count)
         }
     }
+}
 
-    /// gnd_add(self, chans, /)
-    /// --
-    ///
-    /// Connect selected channels to hard ground. Unlike
-    /// :meth:`~pyarc2.Instrument.connect_to_gnd` this function will not clear
-    /// previously grounded channels, only add to those.
-    fn gnd_add<'py>(mut slf: PyRefMut<'py, Self>, chans: PyReadonlyArray1<'py, usize>)
-        -> PyResult<PyRefMut<'py, Self>> {
+impl MACEncoding {
 
-        let slice = chans.as_slice().unwrap();
-        match slf._instrument.gnd_add(slice) {
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
+    /// Largest input value that can be represented with this encoding
+    fn max_value(&self) -> u32 {
+        match self {
+            MACEncoding::BitSerial(bits) => {
+                if *bits >= 32 { u32::MAX } else { (1u32 << bits) - 1 }
+            },
+            MACEncoding::PulseCount(count) => *count
         }
-
     }
 
-    /// gnd_remove(self, chans, /)
-    /// --
-    ///
-    /// Disconnect selected channels from hard ground. Unlike
-    /// :meth:`~pyarc2.Instrument.connect_to_gnd` this function will not clear
-    /// previously grounded channels, only remove from those.
-    fn gnd_remove<'py>(mut slf: PyRefMut<'py, Self>, chans: PyReadonlyArray1<'py, usize>)
-        -> PyResult<PyRefMut<'py, Self>> {
-
-        let slice = chans.as_slice().unwrap();
-        match slf._instrument.gnd_remove(slice) {
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
+    /// Number of read steps required to apply the inputs
+    fn step_count(&self) -> u32 {
+        match self {
+            MACEncoding::BitSerial(bits) => *bits,
+            MACEncoding::PulseCount(count) => *count
         }
+    }
 
+    /// Channel configuration of read step ``k`` along with the weight of
+    /// the step during accumulation.
+    fn step(&self, k: u32, inputs: &[(usize, u32)], vread: f32) -> (Vec<(u16, f32)>, f32) {
+        match self {
+            MACEncoding::BitSerial(_) => {
+                let step = inputs.iter().map(|(chan, val)| {
+                    let v = if (val >> k) & 1 == 1 { vread } else { 0.0 };
+                    (*chan as u16, v)
+                }).collect();
+                (step, (1u64 << k) as f32)
+            },
+            MACEncoding::PulseCount(_) => {
+                let step = inputs.iter().map(|(chan, val)| {
+                    let v = if *val > k { vread } else { 0.0 };
+                    (*chan as u16, v)
+                }).collect();
+                (step, 1.0)
+            }
+        }
     }
+}
 
-    /// connect_to_ac_gnd(self, chans, /)
-    /// --
+/// Safety limits enforced by :class:`pyarc2.Instrument` before any biasing
+/// operation is queued. Limits that are ``None`` are not enforced. Voltage
+/// limits apply to every channel that is biased by an operation, including
+/// read-outs, whereas pulse width, pulse count and dose limits only apply to
+/// pulsing operations. Pulse count and dose are accumulated per crosspoint
+/// (device); the dose is calculated as the sum of V²·t of every pulse
+/// applied to a device (in V²·s) which is proportional to the energy
+/// delivered to it. Operations above ``soft_voltage`` will only be
+/// allowed after the instrument has been armed with
+/// :meth:`~pyarc2.Instrument.arm`. A violation raises a
+/// :class:`~pyarc2.SafetyLimitError`.
+///
+/// >>> limits = SafetyLimits(max_voltage=5.0, max_pulse_width=1_000_000,
+/// >>>     soft_voltage=3.0)
+/// >>> limits.set_channel_voltage(7, 2.0) # never exceed 2 V on channel 7
+/// >>> arc.set_safety_limits(limits)
+///
+/// :param float max_voltage: Maximum absolute voltage on any channel
+/// :param int max_pulse_width: Maximum pulse width in nanoseconds
+/// :param int max_pulses: Maximum number of pulses per device
+/// :param float max_dose: Maximum cumulative V²·s per device
+/// :param float soft_voltage: Absolute voltage above which the instrument
+///                            must be armed
+#[pyclass(name="SafetyLimits", module="pyarc2")]
+#[derive(Clone, Default)]
+struct PySafetyLimits {
+    /// Maximum absolute voltage on any channel
+    #[pyo3(get, set)]
+    max_voltage: Option<f32>,
+    /// Maximum pulse width in nanoseconds
+    #[pyo3(get, set)]
+    max_pulse_width: Option<u128>,
+    /// Maximum number of pulses per device
+    #[pyo3(get, set)]
+    max_pulses: Option<u64>,
+    /// Maximum cumulative V²·s per device
+    #[pyo3(get, set)]
+    max_dose: Option<f64>,
+    /// Absolute voltage above which the instrument must be armed
+    #[pyo3(get, set)]
+    soft_voltage: Option<f32>,
+    channel_voltage: HashMap<usize, f32>
+}
+
+#[pymethods]
+impl PySafetyLimits {
+
+    #[new]
+    #[pyo3(signature = (max_voltage=None, max_pulse_width=None, max_pulses=None,
+        max_dose=None, soft_voltage=None))]
+    fn new(max_voltage: Option<f32>, max_pulse_width: Option<u128>, max_pulses: Option<u64>,
+        max_dose: Option<f64>, soft_voltage: Option<f32>) -> Self {
+        PySafetyLimits {
+            max_voltage,
+            max_pulse_width,
+            max_pulses,
+            max_dose,
+            soft_voltage,
+            channel_voltage: HashMap::new()
+        }
+    }
+
+    /// set_channel_voltage(self, chan, voltage, /)
+    /// --
+    ///
+    /// Set the maximum absolute voltage of a specific channel. Use ``None``
+    /// to remove the channel-specific limit.
+    ///
+    /// :param int chan: The channel to limit
+    /// :param float voltage: The maximum absolute voltage for ``chan``
+    #[pyo3(signature = (chan, voltage))]
+    fn set_channel_voltage(&mut self, chan: usize, voltage: Option<f32>) -> PyResult<()> {
+        if chan >= 64 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Channel {} is out of range (0-63)", chan)));
+        }
+        match voltage {
+            Some(v) => { self.channel_voltage.insert(chan, v.abs()); },
+            None => { self.channel_voltage.remove(&chan); }
+        };
+        Ok(())
+    }
+
+    /// channel_voltage(self, chan, /)
+    /// --
+    ///
+    /// Get the channel-specific voltage limit of a channel, if any.
+    ///
+    /// :param int chan: The channel to query
+    /// :return: The maximum absolute voltage for ``chan`` or ``None``
+    fn channel_voltage(&self, chan: usize) -> Option<f32> {
+        self.channel_voltage.get(&chan).copied()
+    }
+}
+
+create_exception!(pyarc2, SafetyLimitError, exceptions::PyException,
+    "Raised when an operation would violate the configured safety limits \
+    (see SafetyLimits). The offending operation is not queued.");
+
+/// Accumulated pulsing history of a single device
+#[derive(Clone, Copy, Default)]
+struct Exposure {
+    pulses: u64,
+    dose: f64
+}
+
+/// Exposure that a checked pulsing operation adds to its devices. It is
+/// returned by the safety checks and must be charged with
+/// ``PyInstrument::charge`` once the operation has been queued successfully.
+#[must_use]
+#[derive(Default)]
+struct PendingExposure(Vec<((usize, usize), Exposure)>);
+
+impl PendingExposure {
+    fn add(&mut self, device: (usize, usize), pulses: u64, dose: f64) {
+        match self.0.iter_mut().find(|(d, _)| *d == device) {
+            Some((_, exposure)) => {
+                exposure.pulses += pulses;
+                exposure.dose += dose;
+            },
+            None => self.0.push((device, Exposure { pulses, dose }))
+        }
+    }
+
+    /// Exposure already pending for ``device``
+    fn get(&self, device: &(usize, usize)) -> Exposure {
+        self.0.iter().find(|(d, _)| d == device).map(|(_, e)| *e).unwrap_or_default()
+    }
+}
+
+/// Dose in V²·s added by ``count`` pulses of ``voltage`` and ``nanos``
+/// duration
+fn pulse_dose(voltage: f32, nanos: u128, count: u64) -> f64 {
+    (count as f64) * (voltage as f64).powi(2) * (nanos as f64) * 1e-9
+}
+
+/// Channels that cross ``chan`` in the standard 32×32 layout. Bitline
+/// channels (0–15 and 32–47) cross wordline channels (16–31 and 48–63)
+/// and vice versa.
+fn crossing_channels(chan: usize) -> Vec<usize> {
+    let is_bitline = |c: usize| (c % 32) < 16;
+    (0..64usize).filter(|c| is_bitline(*c) != is_bitline(chan)).collect()
+}
+
+/// Make sure every channel in ``chans`` is a valid channel number
+fn check_channels<'a, I: IntoIterator<Item=&'a usize>>(chans: I) -> PyResult<()> {
+    for chan in chans {
+        if *chan >= 64 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Channel {} is out of range (0-63)", chan)));
+        }
+    }
+    Ok(())
+}
+
+/// Number of values in a ramp from ``start`` to ``stop`` (inclusive) in
+/// steps of ``step``. The quotient is taken in f64 and allowed to fall
+/// short of a whole number by ``RAMP_STEP_TOLERANCE`` so that rounding
+/// errors of the f32 arguments (``0.2`` is ``0.200000003``) do not drop
+/// the last step.
+fn ramp_steps(start: f32, step: f32, stop: f32) -> usize {
+    let steps = (stop as f64 - start as f64) / (step as f64);
+    (steps + RAMP_STEP_TOLERANCE).floor().max(0.0) as usize + 1
+}
+
+/// Value ``idx`` of a ramp starting at ``start`` in steps of ``step``
+fn ramp_value(start: f32, step: f32, idx: usize) -> f32 {
+    (start as f64 + (idx as f64) * (step as f64)) as f32
+}
+
+/// Voltages applied by an open (high speed) pulse: every channel is held at
+/// its normal voltage and pulsed to its pulse voltage if its cluster is
+/// pulsed at all.
+fn open_pulse_voltages(chans: &[(usize, f32, f32)], cl_nanos: &[Option<u128>; 8])
+    -> Vec<(usize, f32)> {
+    let mut config: Vec<(usize, f32)> = Vec::with_capacity(2 * chans.len());
+    for (chan, vpulse, vnormal) in chans {
+        config.push((*chan, *vnormal));
+        if let Some(Some(_)) = cl_nanos.get(chan / 8) {
+            config.push((*chan, *vpulse));
+        }
+    }
+    config
+}
+
+/// Normalised identifier of the crosspoint between two channels
+fn device_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// All crosspoints of the standard 32×32 layout
+fn all_devices() -> Vec<(usize, usize)> {
+    let bitlines: Vec<usize> = (0..64usize).filter(|c| (c % 32) < 16).collect();
+    let mut devices = Vec::with_capacity(1024);
+    for b in &bitlines {
+        for w in crossing_channels(*b) {
+            devices.push(device_key(*b, w));
+        }
+    }
+    devices
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum ChannelMode {
+    Arbitrary,
+    HighSpeed,
+    Open,
+    HardGnd,
+    AcGnd,
+    CurrentSource
+}
+
+/// Operating mode of an ArC TWO channel as reported by
+/// :meth:`pyarc2.Instrument.channel_state`.
+///
+/// :var Arbitrary: Channel is driven by its DAC at an arbitrary voltage
+/// :var HighSpeed: Channel is driven by the high speed pulse drivers
+/// :var Open: Channel is disconnected from the DACs
+/// :var HardGnd: Channel is connected to hard ground
+/// :var AcGnd: Channel is connected to AC ground
+/// :var CurrentSource: Channel is driven by the current source
+#[pyclass(name="ChannelMode", module="pyarc2")]
+#[derive(Clone)]
+struct PyChannelMode { _inner: ChannelMode }
+
+#[allow(non_snake_case)]
+#[pymethods]
+impl PyChannelMode {
+
+    #[classattr]
+    fn Arbitrary() -> PyChannelMode {
+        PyChannelMode { _inner: ChannelMode::Arbitrary }
+    }
+
+    #[classattr]
+    fn HighSpeed() -> PyChannelMode {
+        PyChannelMode { _inner: ChannelMode::HighSpeed }
+    }
+
+    #[classattr]
+    fn Open() -> PyChannelMode {
+        PyChannelMode { _inner: ChannelMode::Open }
+    }
+
+    #[classattr]
+    fn HardGnd() -> PyChannelMode {
+        PyChannelMode { _inner: ChannelMode::HardGnd }
+    }
+
+    #[classattr]
+    fn AcGnd() -> PyChannelMode {
+        PyChannelMode { _inner: ChannelMode::AcGnd }
+    }
+
+    #[classattr]
+    fn CurrentSource() -> PyChannelMode {
+        PyChannelMode { _inner: ChannelMode::CurrentSource }
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self._inner == other._inner
+    }
+
+    fn __hash__(&self) -> u64 {
+        self._inner as u64
+    }
+
+    fn __str__(&self) -> &'static str {
+        match self._inner {
+            ChannelMode::Arbitrary => "ChannelMode.Arbitrary",
+            ChannelMode::HighSpeed => "ChannelMode.HighSpeed",
+            ChannelMode::Open => "ChannelMode.Open",
+            ChannelMode::HardGnd => "ChannelMode.HardGnd",
+            ChannelMode::AcGnd => "ChannelMode.AcGnd",
+            ChannelMode::CurrentSource => "ChannelMode.CurrentSource"
+        }
+    }
+
+    fn __repr__(&self) -> &'static str {
+        match self._inner {
+            ChannelMode::Arbitrary => "ChannelMode<Arbitrary>",
+            ChannelMode::HighSpeed => "ChannelMode<HighSpeed>",
+            ChannelMode::Open => "ChannelMode<Open>",
+            ChannelMode::HardGnd => "ChannelMode<HardGnd>",
+            ChannelMode::AcGnd => "ChannelMode<AcGnd>",
+            ChannelMode::CurrentSource => "ChannelMode<CurrentSource>"
+        }
+    }
+}
+
+impl From<ChannelMode> for PyChannelMode {
+    fn from(mode: ChannelMode) -> Self {
+        PyChannelMode { _inner: mode }
+    }
+}
+
+/// Tracked configuration of a single channel. ``mode`` is the driver
+/// configuration of the channel whereas the ground flags are tracked
+/// separately as grounding does not alter the driver configuration.
+#[derive(Clone, Copy)]
+struct ChannelState {
+    mode: ChannelMode,
+    voltage: f32,
+    range: OutputRange,
+    gnd: bool,
+    acgnd: bool
+}
+
+impl Default for ChannelState {
+    fn default() -> Self {
+        ChannelState {
+            mode: ChannelMode::Arbitrary,
+            voltage: 0.0,
+            range: OutputRange::STD,
+            gnd: false,
+            acgnd: false
+        }
+    }
+}
+
+impl ChannelState {
+
+    /// The effective mode of the channel; grounds take precedence over
+    /// the driver configuration.
+    fn effective_mode(&self) -> ChannelMode {
+        if self.gnd {
+            ChannelMode::HardGnd
+        } else if self.acgnd {
+            ChannelMode::AcGnd
+        } else {
+            self.mode
+        }
+    }
+}
+
+/// Keeps track of the state of all 64 channels as commands are queued.
+/// Composite operations (reads, pulses, ramps, etc.) are assumed to leave
+/// the channels they use in arbitrary voltage operation at 0.0 V.
+#[derive(Clone)]
+struct ChannelTracker {
+    chans: [ChannelState; 64]
+}
+
+impl Default for ChannelTracker {
+    fn default() -> Self {
+        ChannelTracker { chans: [ChannelState::default(); 64] }
+    }
+}
+
+impl ChannelTracker {
+
+    fn ground_all(&mut self) {
+        for state in self.chans.iter_mut() {
+            state.mode = ChannelMode::Arbitrary;
+            state.voltage = 0.0;
+        }
+    }
+
+    fn ground_all_fast(&mut self) {
+        for state in self.chans.iter_mut() {
+            state.voltage = 0.0;
+        }
+    }
+
+    fn float_all(&mut self) {
+        for state in self.chans.iter_mut() {
+            state.mode = ChannelMode::Open;
+        }
+    }
+
+    fn open(&mut self, chans: &[usize]) {
+        for chan in chans.iter().filter(|c| **c < 64) {
+            self.chans[*chan].mode = ChannelMode::Open;
+        }
+    }
+
+    fn configure(&mut self, config: &[(u16, f32)], base: Option<f32>) {
+        if let Some(v) = base {
+            for state in self.chans.iter_mut() {
+                state.mode = ChannelMode::Arbitrary;
+                state.voltage = v;
+            }
+        }
+        for (chan, voltage) in config.iter().filter(|(c, _)| (*c as usize) < 64) {
+            let state = &mut self.chans[*chan as usize];
+            state.mode = ChannelMode::Arbitrary;
+            state.voltage = *voltage;
+        }
+    }
+
+    fn high_speed(&mut self, chans: &[(usize, f32, f32)]) {
+        for (chan, _, normal) in chans.iter().filter(|(c, _, _)| *c < 64) {
+            let state = &mut self.chans[*chan];
+            state.mode = ChannelMode::HighSpeed;
+            state.voltage = *normal;
+        }
+    }
+
+    fn current_source(&mut self, chans: &[usize]) {
+        for chan in chans.iter().filter(|c| **c < 64) {
+            let state = &mut self.chans[*chan];
+            state.mode = ChannelMode::CurrentSource;
+            state.voltage = 0.0;
+        }
+    }
+
+    fn soft_ground(&mut self, chans: &[usize]) {
+        self.configure(&chans.iter().map(|c| (*c as u16, 0.0)).collect::<Vec<_>>(), None);
+    }
+
+    fn set_gnd(&mut self, chans: &[usize], add: bool, clear: bool) {
+        if clear {
+            for state in self.chans.iter_mut() {
+                state.gnd = false;
+            }
+        }
+        for chan in chans.iter().filter(|c| **c < 64) {
+            self.chans[*chan].gnd = add;
+        }
+    }
+
+    fn set_acgnd(&mut self, chans: &[usize], add: bool, clear: bool) {
+        if clear {
+            for state in self.chans.iter_mut() {
+                state.acgnd = false;
+            }
+        }
+        for chan in chans.iter().filter(|c| **c < 64) {
+            self.chans[*chan].acgnd = add;
+        }
+    }
+
+    fn set_range(&mut self, chans: &[usize], range: OutputRange) {
+        for chan in chans.iter().filter(|c| **c < 64) {
+            self.chans[*chan].range = range;
+        }
+    }
+}
+
+/// All AUX DAC functions in the order they are tracked
+const AUX_FNS: [AuxDACFn; 9] = [
+    AuxDACFn::SELL, AuxDACFn::SELH,
+    AuxDACFn::ARB1, AuxDACFn::ARB2, AuxDACFn::ARB3, AuxDACFn::ARB4,
+    AuxDACFn::CREF, AuxDACFn::CSET, AuxDACFn::LGC
+];
+
+/// Names of the AUX DAC functions in the same order as ``AUX_FNS``
+const AUX_NAMES: [&str; 9] = [
+    "SELL", "SELH", "ARB1", "ARB2", "ARB3", "ARB4", "CREF", "CSET", "LGC"
+];
+
+/// Maximum output voltage of ``range``
+fn range_max(range: &OutputRange) -> f32 {
+    match range {
+        OutputRange::STD => STD_RANGE_MAX,
+        OutputRange::EXT => EXT_RANGE_MAX
+    }
+}
+
+/// Voltage step of a single DAC code in ``range``
+fn dac_lsb(range: &OutputRange) -> f64 {
+    2.0 * (range_max(range) as f64) / 65536.0
+}
+
+/// DAC code for ``voltage`` in ``range``. The channel DACs are offset
+/// binary: ``DAC_ZERO_CODE`` is 0.0 V and every code adds one ``dac_lsb``,
+/// so the range spans from ``-max`` (code 0) to ``max - lsb`` (code
+/// 0xFFFF). Voltages beyond that are clamped.
+fn dac_code(voltage: f32, range: &OutputRange) -> u16 {
+    let steps = ((voltage as f64) / dac_lsb(range)).round();
+    (steps + DAC_ZERO_CODE as f64).clamp(0.0, u16::MAX as f64) as u16
+}
+
+/// Voltage produced by DAC ``code`` in ``range``
+fn dac_voltage(code: u16, range: &OutputRange) -> f32 {
+    ((code as f64 - DAC_ZERO_CODE as f64) * dac_lsb(range)) as f32
+}
+
+/// Voltage actually produced when programming ``voltage`` in ``range``
+fn quantise(voltage: f32, range: &OutputRange) -> f32 {
+    dac_voltage(dac_code(voltage, range), range)
+}
+
+/// AUX DAC function from its name in ``AUX_NAMES``
+fn aux_from_name(name: &str) -> Option<AuxDACFn> {
+    AUX_NAMES.iter().position(|n| *n == name).map(|idx| AUX_FNS[idx])
+}
+
+/// ``{function name: voltage}`` view of a list of AUX DAC voltages
+fn aux_by_name(voltages: &[(AuxDACFn, f32)]) -> BTreeMap<String, f32> {
+    voltages.iter()
+        .map(|(dac, v)| (AUX_NAMES[aux_index(*dac)].to_string(), *v))
+        .collect()
+}
+
+/// Position of an AUX DAC function in ``AUX_FNS``
+fn aux_index(func: AuxDACFn) -> usize {
+    match func {
+        AuxDACFn::SELL => 0,
+        AuxDACFn::SELH => 1,
+        AuxDACFn::ARB1 => 2,
+        AuxDACFn::ARB2 => 3,
+        AuxDACFn::ARB3 => 4,
+        AuxDACFn::ARB4 => 5,
+        AuxDACFn::CREF => 6,
+        AuxDACFn::CSET => 7,
+        AuxDACFn::LGC => 8
+    }
+}
+
+fn logic_level_name(level: &LogicLevel) -> &'static str {
+    match level {
+        LogicLevel::LL1V8 => "LL1V8",
+        LogicLevel::LL3V3 => "LL3V3",
+        LogicLevel::LL5V => "LL5V"
+    }
+}
+
+fn logic_level_volts(level: &LogicLevel) -> f32 {
+    match level {
+        LogicLevel::LL1V8 => 1.8,
+        LogicLevel::LL3V3 => 3.3,
+        LogicLevel::LL5V => 5.0
+    }
+}
+
+fn logic_level_from_name(name: &str) -> Option<LogicLevel> {
+    match name {
+        "LL1V8" => Some(LogicLevel::LL1V8),
+        "LL3V3" => Some(LogicLevel::LL3V3),
+        "LL5V" => Some(LogicLevel::LL5V),
+        _ => None
+    }
+}
+
+fn control_mode_name(mode: &ControlMode) -> &'static str {
+    match mode {
+        ControlMode::Header => "Header",
+        ControlMode::Internal => "Internal"
+    }
+}
+
+fn control_mode_from_name(name: &str) -> Option<ControlMode> {
+    match name {
+        "Header" => Some(ControlMode::Header),
+        "Internal" => Some(ControlMode::Internal),
+        _ => None
+    }
+}
+
+/// Tracked configuration of the peripheral ArC TWO circuitry. Values are
+/// ``None`` until they have been configured through the library as their
+/// power-on state is not known.
+#[derive(Clone, Default)]
+struct PeripheralState {
+    selectors: Option<Vec<usize>>,
+    aux: [Option<f32>; 9],
+    logic: Option<(u32, [IODir; 4])>,
+    logic_level: Option<LogicLevel>,
+    control: Option<ControlMode>
+}
+
+impl PeripheralState {
+
+    /// Last programmed voltage of every AUX DAC function that has been
+    /// configured so far
+    fn aux_voltages(&self) -> Vec<(AuxDACFn, f32)> {
+        AUX_FNS.iter().zip(self.aux.iter())
+            .filter_map(|(dac, v)| v.map(|v| (*dac, v)))
+            .collect()
+    }
+}
+
+/// Named GPIO pins and the logic level declared by the connected peripheral
+#[derive(Clone, Default)]
+struct GpioState {
+    pins: BTreeMap<String, (usize, IODir)>,
+    peripheral_level: Option<LogicLevel>
+}
+
+impl GpioState {
+
+    /// Direction of each GPIO cluster as dictated by the named pins; ``None``
+    /// for clusters without any named pins.
+    fn cluster_dirs(&self) -> [Option<IODir>; 4] {
+        let mut dirs: [Option<IODir>; 4] = [None; 4];
+        for (pin, dir) in self.pins.values() {
+            dirs[pin / 8] = Some(*dir);
+        }
+        dirs
+    }
+
+    /// Make sure the cluster directions in ``dirs`` agree with the named pins
+    fn check_dirs(&self, dirs: &[IODir; 4]) -> PyResult<()> {
+        let dirname = |d: IODir| if d == IODir::OUT { "output" } else { "input" };
+        for (name, (pin, dir)) in &self.pins {
+            if dirs[pin / 8] != *dir {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Cluster {} (GPIO {}-{}) cannot be an {} as pin \"{}\" ({}) \
+                        is an {}", pin / 8, (pin / 8) * 8, (pin / 8) * 8 + 7,
+                        dirname(dirs[pin / 8]), name, pin, dirname(*dir))));
+            }
+        }
+        Ok(())
+    }
+
+    fn pin(&self, name: &str) -> PyResult<(usize, IODir)> {
+        self.pins.get(name).copied().ok_or_else(||
+            exceptions::PyKeyError::new_err(format!("No GPIO pin named \"{}\"", name)))
+    }
+}
+
+/// Access transistor (1T1R) configuration: the selector gating each
+/// channel and the gate voltage applied through ``SELH`` when enabled
+#[derive(Clone, Default)]
+struct GateState {
+    map: BTreeMap<usize, usize>,
+    voltage: Option<f32>
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct ChannelSnapshot {
+    mode: ChannelMode,
+    voltage: f32,
+    ext_range: bool,
+    gnd: bool,
+    acgnd: bool
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ConfigSnapshot {
+    channels: Vec<ChannelSnapshot>,
+    selectors: Option<Vec<usize>>,
+    aux: BTreeMap<String, f32>,
+    logic_mask: Option<u32>,
+    logic_outputs: Option<[bool; 4]>,
+    logic_level: Option<String>,
+    control_mode: Option<String>
+}
+
+impl ConfigSnapshot {
+
+    /// Make sure all values of the snapshot are within the capabilities of
+    /// the instrument. Snapshots are checked when loaded and again before
+    /// being restored.
+    fn validate(&self) -> PyResult<()> {
+
+        if self.channels.len() != 64 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Snapshot must contain 64 channels, found {}", self.channels.len())));
+        }
+
+        for (chan, state) in self.channels.iter().enumerate() {
+            let max = if state.ext_range { EXT_RANGE_MAX } else { STD_RANGE_MAX };
+            if !state.voltage.is_finite() || state.voltage.abs() > max {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Voltage {} V of channel {} is outside its ±{} V range",
+                        state.voltage, chan, max)));
+            }
+        }
+
+        for (name, voltage) in &self.aux {
+            if !AUX_NAMES.contains(&name.as_str()) {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Unknown AUX DAC function {}", name)));
+            }
+            if !voltage.is_finite() || voltage.abs() > STD_RANGE_MAX {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Voltage {} V of AUX DAC {} is outside the ±{} V range",
+                        voltage, name, STD_RANGE_MAX)));
+            }
+        }
+
+        if let Some(selectors) = &self.selectors {
+            if let Some(sel) = selectors.iter().find(|s| **s >= 32) {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Selector {} is out of range (0-31)", sel)));
+            }
+        }
+
+        if let Some(name) = &self.logic_level {
+            if logic_level_from_name(name).is_none() {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Unknown logic level {}", name)));
+            }
+        }
+
+        if let Some(name) = &self.control_mode {
+            if control_mode_from_name(name).is_none() {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Unknown control mode {}", name)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A snapshot of the instrument configuration as tracked by the library.
+/// Snapshots are created with :meth:`pyarc2.Instrument.snapshot` and
+/// applied with :meth:`pyarc2.Instrument.restore`. They cover channel
+/// voltages, modes and ranges, hard and AC ground sets, selectors, AUX DAC
+/// voltages, GPIO state and direction, logic level and control mode. Parts
+/// of the configuration that have never been set through the library are
+/// not included. Snapshots can be serialised into JSON with
+/// :meth:`~pyarc2.Snapshot.to_json` and loaded back with
+/// :meth:`~pyarc2.Snapshot.from_json`.
+#[pyclass(name="Snapshot", module="pyarc2")]
+#[derive(Clone)]
+struct PySnapshot { _inner: ConfigSnapshot }
+
+#[pymethods]
+impl PySnapshot {
+
+    /// to_json(self, /)
+    /// --
+    ///
+    /// Serialise this snapshot into a JSON string.
+    ///
+    /// :rtype: str
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self._inner)
+            .map_err(|err| exceptions::PyValueError::new_err(format!("{}", err)))
+    }
+
+    /// from_json(data, /)
+    /// --
+    ///
+    /// Load a snapshot from a JSON string created by
+    /// :meth:`~pyarc2.Snapshot.to_json`.
+    ///
+    /// :param str data: The serialised snapshot
+    /// :return: A new :class:`~pyarc2.Snapshot`
+    /// :raises ValueError: If ``data`` is not a valid snapshot or contains
+    ///                     voltages that are not finite or out of range
+    #[staticmethod]
+    fn from_json(data: &str) -> PyResult<PySnapshot> {
+        let inner: ConfigSnapshot = serde_json::from_str(data)
+            .map_err(|err| exceptions::PyValueError::new_err(format!("{}", err)))?;
+        inner.validate()?;
+        Ok(PySnapshot { _inner: inner })
+    }
+
+    /// The selectors toggled high or ``None`` if not tracked
+    #[getter]
+    fn selectors(&self) -> Option<Vec<usize>> {
+        self._inner.selectors.clone()
+    }
+
+    /// The AUX DAC voltages as a dict of ``{function name: voltage}``
+    #[getter]
+    fn aux(&self) -> BTreeMap<String, f32> {
+        self._inner.aux.clone()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CalibrationData {
+    offsets: Vec<f32>,
+    gains: Vec<f32>
+}
+
+/// Per-channel current calibration. Currents read from a channel are
+/// corrected as ``(current - offset) * gain`` where ``offset`` is the
+/// current the channel reports at zero bias and ``gain`` compensates the
+/// gain error of its read-out path. Calibrations are usually created with
+/// :meth:`pyarc2.Instrument.measure_calibration` and stored as JSON files
+/// with :meth:`~pyarc2.Calibration.save`. A calibration is applied to the
+/// instrument with :meth:`pyarc2.Instrument.set_calibration`.
+///
+/// >>> cal = Calibration.load('arc2-unit3.json')
+/// >>> arc.set_calibration(cal)
+///
+/// :param offsets: 64 current offsets in A or ``None`` for zero offsets
+/// :param gains: 64 gain factors or ``None`` for unity gains
+/// :raises ValueError: If ``offsets`` or ``gains`` do not have 64 entries
+#[pyclass(name="Calibration", module="pyarc2")]
+#[derive(Clone)]
+struct PyCalibration { _inner: CalibrationData }
+
+impl PyCalibration {
+
+    fn validate(data: CalibrationData) -> PyResult<PyCalibration> {
+        if data.offsets.len() != 64 || data.gains.len() != 64 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Calibration requires 64 offsets and gains, found {} and {}",
+                    data.offsets.len(), data.gains.len())));
+        }
+        Ok(PyCalibration { _inner: data })
+    }
+
+    /// Correct a current read from ``chan``
+    fn apply(&self, chan: usize, current: f32) -> f32 {
+        match (self._inner.offsets.get(chan), self._inner.gains.get(chan)) {
+            (Some(offset), Some(gain)) => (current - offset) * gain,
+            _ => current
+        }
+    }
+}
+
+#[pymethods]
+impl PyCalibration {
+
+    #[new]
+    #[pyo3(signature = (offsets=None, gains=None))]
+    fn new(offsets: Option<Vec<f32>>, gains: Option<Vec<f32>>) -> PyResult<Self> {
+        PyCalibration::validate(CalibrationData {
+            offsets: offsets.unwrap_or_else(|| vec![0.0; 64]),
+            gains: gains.unwrap_or_else(|| vec![1.0; 64])
+        })
+    }
+
+    /// The current offset of every channel in A
+    #[getter]
+    fn offsets(&self) -> Vec<f32> {
+        self._inner.offsets.clone()
+    }
+
+    /// The gain factor of every channel
+    #[getter]
+    fn gains(&self) -> Vec<f32> {
+        self._inner.gains.clone()
+    }
+
+    /// correct(self, chan, current, /)
+    /// --
+    ///
+    /// Correct a current read from ``chan``.
+    ///
+    /// :param int chan: The channel the current was read from
+    /// :param float current: The raw current
+    /// :rtype: float
+    fn correct(&self, chan: usize, current: f32) -> f32 {
+        self.apply(chan, current)
+    }
+
+    /// to_json(self, /)
+    /// --
+    ///
+    /// Serialise this calibration into a JSON string.
+    ///
+    /// :rtype: str
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string_pretty(&self._inner)
+            .map_err(|err| exceptions::PyValueError::new_err(format!("{}", err)))
+    }
+
+    /// from_json(data, /)
+    /// --
+    ///
+    /// Load a calibration from a JSON string created by
+    /// :meth:`~pyarc2.Calibration.to_json`.
+    ///
+    /// :param str data: The serialised calibration
+    /// :return: A new :class:`~pyarc2.Calibration`
+    /// :raises ValueError: If ``data`` is not a valid calibration
+    #[staticmethod]
+    fn from_json(data: &str) -> PyResult<PyCalibration> {
+        let inner: CalibrationData = serde_json::from_str(data)
+            .map_err(|err| exceptions::PyValueError::new_err(format!("{}", err)))?;
+        PyCalibration::validate(inner)
+    }
+
+    /// save(self, path, /)
+    /// --
+    ///
+    /// Write this calibration to a JSON file. The file contains two arrays
+    /// of 64 numbers each, ``offsets`` and ``gains``, in channel order.
+    ///
+    /// :param str path: The file to write
+    fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.to_json()?)
+            .map_err(|err| exceptions::PyIOError::new_err(format!("{}: {}", path, err)))
+    }
+
+    /// load(path, /)
+    /// --
+    ///
+    /// Read a calibration from a JSON file written by
+    /// :meth:`~pyarc2.Calibration.save`.
+    ///
+    /// :param str path: The file to read
+    /// :return: A new :class:`~pyarc2.Calibration`
+    /// :raises ValueError: If the file is not a valid calibration
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<PyCalibration> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|err| exceptions::PyIOError::new_err(format!("{}: {}", path, err)))?;
+        PyCalibration::from_json(&data)
+    }
+}
+
+/// State of a single ArC TWO channel as tracked by the instrument.
+/// Returned by :meth:`pyarc2.Instrument.channel_state`.
+#[pyclass(name="ChannelState", module="pyarc2")]
+#[derive(Clone)]
+struct PyChannelState {
+    /// The channel number
+    #[pyo3(get)]
+    channel: usize,
+    /// The effective mode of the channel as a :class:`~pyarc2.ChannelMode`
+    #[pyo3(get)]
+    mode: PyChannelMode,
+    /// The last programmed voltage of the channel
+    #[pyo3(get)]
+    voltage: f32,
+    /// The last programmed voltage of the channel after DAC quantisation
+    #[pyo3(get)]
+    applied_voltage: f32,
+    /// The output range of the channel as a :class:`~pyarc2.OutputRange`
+    #[pyo3(get)]
+    range: PyOutputRange,
+    /// Whether the channel is connected to hard ground
+    #[pyo3(get)]
+    grounded: bool,
+    /// Whether the channel is connected to AC ground
+    #[pyo3(get)]
+    ac_grounded: bool
+}
+
+#[pymethods]
+impl PyChannelState {
+
+    fn __repr__(&self) -> String {
+        format!("ChannelState<{}: {}, {} V, {}>", self.channel,
+            self.mode.__str__(), self.voltage, self.range.__str__())
+    }
+}
+
+impl PyChannelState {
+    fn new(channel: usize, state: &ChannelState) -> Self {
+        PyChannelState {
+            channel,
+            mode: state.effective_mode().into(),
+            voltage: state.voltage,
+            applied_voltage: quantise(state.voltage, &state.range),
+            range: state.range.into(),
+            grounded: state.gnd,
+            ac_grounded: state.acgnd
+        }
+    }
+}
+
+/// Self-test result of a single channel. Part of a
+/// :class:`~pyarc2.SelfTestReport`.
+#[pyclass(name="ChannelReport", module="pyarc2")]
+#[derive(Clone)]
+struct PyChannelReport {
+    /// The channel number
+    #[pyo3(get)]
+    channel: usize,
+    /// Largest deviation of the measured from the programmed voltage
+    #[pyo3(get)]
+    voltage_error: f32,
+    /// Current measured at zero bias
+    #[pyo3(get)]
+    leakage: f32,
+    /// Relative error of the current through the known load of the channel
+    /// or ``None`` if the channel has no load
+    #[pyo3(get)]
+    load_error: Option<f32>,
+    /// Description of every failed check
+    #[pyo3(get)]
+    issues: Vec<String>
+}
+
+#[pymethods]
+impl PyChannelReport {
+
+    /// Whether all checks passed
+    #[getter]
+    fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn __repr__(&self) -> String {
+        if self.issues.is_empty() {
+            format!("ChannelReport<{}: OK>", self.channel)
+        } else {
+            format!("ChannelReport<{}: {}>", self.channel, self.issues.join("; "))
+        }
+    }
+}
+
+/// Report of :meth:`pyarc2.Instrument.self_test`
+#[pyclass(name="SelfTestReport", module="pyarc2")]
+#[derive(Clone)]
+struct PySelfTestReport {
+    /// A :class:`~pyarc2.ChannelReport` for each of the 64 channels
+    #[pyo3(get)]
+    channels: Vec<PyChannelReport>
+}
+
+#[pymethods]
+impl PySelfTestReport {
+
+    /// Whether all channels passed
+    #[getter]
+    fn passed(&self) -> bool {
+        self.channels.iter().all(|c| c.issues.is_empty())
+    }
+
+    /// The channels that failed at least one check
+    #[getter]
+    fn failures(&self) -> Vec<usize> {
+        self.channels.iter().filter(|c| !c.issues.is_empty()).map(|c| c.channel).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        let failures = self.failures();
+        if failures.is_empty() {
+            "SelfTestReport<all 64 channels OK>".to_string()
+        } else {
+            format!("SelfTestReport<{} failed: {:?}>", failures.len(), failures)
+        }
+    }
+}
+
+/// Report of :meth:`pyarc2.Instrument.scan_shorts_opens`. Crosspoints
+/// are identified by their ``(bitline, wordline)`` channel pair.
+#[pyclass(name="CrosspointReport", module="pyarc2")]
+#[derive(Clone)]
+struct PyCrosspointReport {
+    currents: Vec<f32>,
+    /// Crosspoints with a current at or above the short threshold
+    #[pyo3(get)]
+    shorts: Vec<(usize, usize)>,
+    /// Crosspoints with a current at or below the open threshold
+    #[pyo3(get)]
+    opens: Vec<(usize, usize)>
+}
+
+#[pymethods]
+impl PyCrosspointReport {
+
+    /// The measured current of every crosspoint as a 32×32 numpy array
+    #[getter]
+    fn currents<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray<f32, Ix2>> {
+        let array = self.currents.clone().into_pyarray_bound(py);
+        array.borrow().reshape((32, 32)).unwrap()
+    }
+
+    /// Number of crosspoints that are neither shorted nor open
+    #[getter]
+    fn ok(&self) -> usize {
+        self.currents.len() - self.shorts.len() - self.opens.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("CrosspointReport<{} OK, {} short, {} open>", self.ok(),
+            self.shorts.len(), self.opens.len())
+    }
+}
+
+/// Catch-all exception for low-level ArC2 errors
+/// --
+#[pyclass(name="ArC2Error", module="pyarc2")]
+struct PyArC2Error { _inner: LLArC2Error }
+
+#[pymethods]
+impl PyArC2Error {
+
+    fn __str__(&self) -> String {
+        let inner = &self._inner;
+        format!("{}", inner)
+    }
+
+}
+
+impl From<LLArC2Error> for PyArC2Error {
+    fn from(err: LLArC2Error) -> Self {
+        PyArC2Error { _inner: err }
+    }
+}
+
+impl From<PyArC2Error> for LLArC2Error {
+    fn from(err: PyArC2Error) -> Self {
+        err._inner
+    }
+}
+
+create_exception!(pyarc2, ArC2Error, exceptions::PyException,
+    "Catch-all exception for low-level ArC2 errors. \
+    There are five broad categories of low-level errors: \
+    (1) FPGA communication errors, (2) Memory access errors \
+    (3) Invalid device ID, (4) Inconsistent ramp errors \
+    and (5) Output buffer access errors");
+
+impl ArC2Error {
+    pub fn new_exception(err: LLArC2Error) -> PyErr {
+        ArC2Error::new_err(PyArC2Error { _inner: err })
+    }
+}
+
+#[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
+#[pyclass(name="InstrumentLL", module="pyarc2", subclass)]
+pub struct PyInstrument {
+    _instrument: Instrument,
+    _limits: PySafetyLimits,
+    _armed: bool,
+    _exposure: HashMap<(usize, usize), Exposure>,
+    _channels: ChannelTracker,
+    _periph: PeripheralState,
+    _aux_presets: BTreeMap<String, Vec<(AuxDACFn, f32)>>,
+    _gpio: GpioState,
+    _gate: GateState,
+    _csrc: Option<(f32, f32)>,
+    _auto_range: bool,
+    _range_switches: Vec<Vec<usize>>,
+    _applied: [Option<f32>; 64],
+    _calibration: Option<PyCalibration>,
+    _correction: bool,
+    _port: i32,
+    _firmware: String
+}
+
+#[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
+impl PyInstrument {
+
+    /// Returns a reference to the underlying Instrument
+    pub fn inner(&self) -> &Instrument {
+        &self._instrument
+    }
+
+    /// Returns a mutable reference to the underlying Instrument
+    pub fn inner_mut(&mut self) -> &mut Instrument {
+        &mut self._instrument
+    }
+
+    /// Check a voltage that is about to be applied on ``chan`` against the
+    /// configured safety limits and the output range of the channel.
+    fn check_voltage(&self, chan: usize, voltage: f32) -> PyResult<()> {
+        self.check_limits(&format!("channel {}", chan), Some(chan), voltage)?;
+        self.check_range(chan, voltage.abs())
+    }
+
+    /// Record the voltages of an operation that has been queued successfully
+    /// as the last applied voltage of their channels. Voltages are stored as
+    /// produced by the DAC in the current range of every channel.
+    fn record_applied<I: IntoIterator<Item=(usize, f32)>>(&mut self, config: I) {
+        for (chan, voltage) in config {
+            if chan < 64 {
+                self._applied[chan] = Some(quantise(voltage, &self._channels.chans[chan].range));
+            }
+        }
+    }
+
+    /// Check ``voltage`` against the configured safety limits. ``target``
+    /// names the output in error messages and ``chan`` selects the
+    /// channel-specific limit, if any.
+    fn check_limits(&self, target: &str, chan: Option<usize>, voltage: f32) -> PyResult<()> {
+
+        let limits = &self._limits;
+        let v = voltage.abs();
+
+        if !voltage.is_finite() {
+            return Err(exceptions::PyValueError::new_err(
+                format!("{} V on {} is not a valid voltage", voltage, target)));
+        }
+
+        if let Some(max) = limits.max_voltage {
+            if v > max {
+                return Err(SafetyLimitError::new_err(
+                    format!("{} V on {} exceeds the maximum voltage of {} V",
+                        voltage, target, max)));
+            }
+        }
+
+        if let Some(max) = chan.and_then(|c| limits.channel_voltage.get(&c)) {
+            if v > *max {
+                return Err(SafetyLimitError::new_err(
+                    format!("{} V on {} exceeds the channel limit of {} V",
+                        voltage, target, max)));
+            }
+        }
+
+        if let Some(soft) = limits.soft_voltage {
+            if v > soft && !self._armed {
+                return Err(SafetyLimitError::new_err(
+                    format!("{} V on {} is above the soft limit of {} V; \
+                        arm the instrument first", voltage, target, soft)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make sure a voltage of magnitude ``v`` fits the tracked output range
+    /// of ``chan``, or will fit once ``queue_ranges`` switches the channel to
+    /// ``EXT`` if automatic range selection is enabled. Nothing is queued.
+    fn check_range(&self, chan: usize, v: f32) -> PyResult<()> {
+
+        if v > EXT_RANGE_MAX {
+            return Err(exceptions::PyValueError::new_err(
+                format!("{} V on channel {} is beyond the maximum output of ±{} V",
+                    v, chan, EXT_RANGE_MAX)));
+        }
+
+        if chan >= 64 || v <= STD_RANGE_MAX || self._channels.chans[chan].range == OutputRange::EXT {
+            return Ok(());
+        }
+
+        if !self._auto_range {
+            return Err(exceptions::PyValueError::new_err(
+                format!("{} V on channel {} is beyond the ±{} V standard range; switch the \
+                    channel to OutputRange.EXT or enable automatic range selection",
+                    v, chan, STD_RANGE_MAX)));
+        }
+
+        Ok(())
+    }
+
+    /// Queue the automatic switch to ``EXT`` of every channel of ``config``
+    /// whose voltage is beyond the standard range. This must be called after
+    /// the whole operation has been validated with ``check_voltage`` and
+    /// right before the operation itself is queued. All switches are
+    /// combined into a single range change command.
+    fn queue_ranges<I: IntoIterator<Item=(usize, f32)>>(&mut self, config: I) -> PyResult<()> {
+
+        if !self._auto_range {
+            return Ok(());
+        }
+
+        let mut chans: Vec<usize> = config.into_iter()
+            .filter(|(chan, v)| *chan < 64 && v.abs() > STD_RANGE_MAX &&
+                self._channels.chans[*chan].range == OutputRange::STD)
+            .map(|(chan, _)| chan)
+            .collect();
+        chans.sort_unstable();
+        chans.dedup();
+
+        if chans.is_empty() {
+            return Ok(());
+        }
+
+        self._instrument.set_channel_range(&chans, &OutputRange::EXT)
+            .map_err(ArC2Error::new_exception)?;
+        self._channels.set_range(&chans, OutputRange::EXT);
+        self._range_switches.push(chans);
+
+        Ok(())
+    }
+
+    /// Check a series of voltages against the configured safety limits
+    fn check_voltages(&self, config: &[(usize, f32)]) -> PyResult<()> {
+        for (chan, voltage) in config {
+            self.check_voltage(*chan, *voltage)?;
+        }
+        Ok(())
+    }
+
+    /// Check ``count`` pulses of ``voltage`` and ``nanos`` duration applied
+    /// through channels ``chans`` on ``devices`` against the safety limits. If
+    /// the check passes the exposure the pulses add to every device is
+    /// returned; it must be charged with ``charge`` once the pulses have been
+    /// queued.
+    fn check_pulses(&mut self, chans: &[usize], devices: &[(usize, usize)], voltage: f32,
+        nanos: u128, count: u64) -> PyResult<PendingExposure> {
+        let mut pending = PendingExposure::default();
+        self.check_pulses_with(&mut pending, chans, devices, voltage, nanos, count)?;
+        Ok(pending)
+    }
+
+    /// Same as ``check_pulses`` but also takes into account and adds to the
+    /// exposure ``pending`` from earlier pulses of the same operation.
+    fn check_pulses_with(&mut self, pending: &mut PendingExposure, chans: &[usize],
+        devices: &[(usize, usize)], voltage: f32, nanos: u128, count: u64) -> PyResult<()> {
+
+        for chan in chans {
+            self.check_voltage(*chan, voltage)?;
+        }
+
+        if let Some(max) = self._limits.max_pulse_width {
+            if nanos > max {
+                return Err(SafetyLimitError::new_err(
+                    format!("Pulse width of {} ns exceeds the maximum of {} ns", nanos, max)));
+            }
+        }
+
+        let dose = pulse_dose(voltage, nanos, count);
+
+        for device in devices {
+            let mut exposure = self._exposure.get(device).copied().unwrap_or_default();
+            let earlier = pending.get(device);
+            exposure.pulses += earlier.pulses;
+            exposure.dose += earlier.dose;
+
+            if let Some(max) = self._limits.max_pulses {
+                if exposure.pulses + count > max {
+                    return Err(SafetyLimitError::new_err(
+                        format!("Device ({}, {}) has received {} pulses; another {} would \
+                            exceed the maximum of {}", device.0, device.1, exposure.pulses,
+                            count, max)));
+                }
+            }
+
+            if let Some(max) = self._limits.max_dose {
+                if exposure.dose + dose > max {
+                    return Err(SafetyLimitError::new_err(
+                        format!("Device ({}, {}) has received a dose of {:.3e} V²·s; \
+                            another {:.3e} V²·s would exceed the maximum of {:.3e} V²·s",
+                            device.0, device.1, exposure.dose, dose, max)));
+                }
+            }
+        }
+
+        for device in devices {
+            pending.add(*device, count, dose);
+        }
+
+        Ok(())
+    }
+
+    /// Add the exposure of a successfully queued operation to its devices
+    fn charge(&mut self, pending: PendingExposure) {
+        for (device, added) in pending.0 {
+            let exposure = self._exposure.entry(device).or_default();
+            exposure.pulses += added.pulses;
+            exposure.dose += added.dose;
+        }
+    }
+
+    /// Check open (high speed) pulses against the safety limits. As there is
+    /// no receiving channel every crosspoint along the pulsed channel is
+    /// considered exposed.
+    fn check_open_pulses(&mut self, chans: &[(usize, f32, f32)], cl_nanos: &[Option<u128>; 8])
+        -> PyResult<PendingExposure> {
+
+        let mut pending = PendingExposure::default();
+
+        for (chan, vpulse, vnormal) in chans {
+            self.check_voltage(*chan, *vnormal)?;
+            if let Some(Some(nanos)) = cl_nanos.get(chan / 8) {
+                let devices: Vec<(usize, usize)> = crossing_channels(*chan).iter()
+                    .map(|c| device_key(*chan, *c)).collect();
+                self.check_pulses_with(&mut pending, &[*chan], &devices, *vpulse, *nanos, 1)?;
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// Capture the tracked configuration of the instrument
+    fn config_snapshot(&self) -> ConfigSnapshot {
+
+        let channels = self._channels.chans.iter().map(|state| {
+            ChannelSnapshot {
+                mode: state.mode,
+                voltage: state.voltage,
+                ext_range: state.range == OutputRange::EXT,
+                gnd: state.gnd,
+                acgnd: state.acgnd
+            }
+        }).collect();
+
+        let aux = aux_by_name(&self._periph.aux_voltages());
+
+        let (logic_mask, logic_outputs) = match &self._periph.logic {
+            Some((mask, dirs)) => {
+                let mut outputs = [true; 4];
+                for (out, dir) in outputs.iter_mut().zip(dirs.iter()) {
+                    *out = *dir == IODir::OUT;
+                }
+                (Some(*mask), Some(outputs))
+            },
+            None => (None, None)
+        };
+
+        ConfigSnapshot {
+            channels,
+            selectors: self._periph.selectors.clone(),
+            aux,
+            logic_mask,
+            logic_outputs,
+            logic_level: self._periph.logic_level.as_ref().map(|l| logic_level_name(l).to_string()),
+            control_mode: self._periph.control.as_ref().map(|c| control_mode_name(c).to_string())
+        }
+    }
+
+    /// Queue the minimal set of commands required to bring the instrument
+    /// from its current tracked configuration to ``target``.
+    fn queue_restore(&mut self, target: &ConfigSnapshot) -> PyResult<()> {
+
+        let current = self.config_snapshot();
+
+        // everything is validated before anything is queued; ranges are
+        // already checked against the target range of every channel
+        target.validate()?;
+        for (chan, (cur, tgt)) in current.channels.iter().zip(target.channels.iter()).enumerate() {
+            if tgt.mode != ChannelMode::Open && tgt.mode != ChannelMode::CurrentSource &&
+                (cur.mode != tgt.mode || cur.voltage != tgt.voltage) {
+                self.check_limits(&format!("channel {}", chan), Some(chan), tgt.voltage)?;
+            }
+        }
+        for (name, voltage) in &target.aux {
+            if current.aux.get(name) != Some(voltage) {
+                self.check_limits(&format!("AUX DAC {}", name), None, *voltage)?;
+            }
+        }
+
+        if let Some(name) = &target.control_mode {
+            if current.control_mode.as_ref() != Some(name) {
+                let mode = control_mode_from_name(name).ok_or_else(||
+                    exceptions::PyValueError::new_err(format!("Unknown control mode {}", name)))?;
+                self._instrument.set_control_mode(mode.clone()).map_err(ArC2Error::new_exception)?;
+                self._periph.control = Some(mode);
+            }
+        }
+
+        if let Some(name) = &target.logic_level {
+            if current.logic_level.as_ref() != Some(name) {
+                let level = logic_level_from_name(name).ok_or_else(||
+                    exceptions::PyValueError::new_err(format!("Unknown logic level {}", name)))?;
+                self._instrument.set_logic_level(level.clone()).map_err(ArC2Error::new_exception)?;
+                self._periph.logic_level = Some(level);
+            }
+        }
+
+        if let (Some(mask), Some(outputs)) = (target.logic_mask, target.logic_outputs) {
+            if current.logic_mask != Some(mask) || current.logic_outputs != Some(outputs) {
+                let dirs = outputs.map(|out| if out { IODir::OUT } else { IODir::IN });
+                self._instrument.set_logic(dirs[0], dirs[1], dirs[2], dirs[3],
+                    &IOMask::from_vals(&[mask])).map_err(ArC2Error::new_exception)?;
+                self._periph.logic = Some((mask, dirs));
+            }
+        }
+
+        let aux: Vec<(AuxDACFn, f32)> = target.aux.iter()
+            .filter(|(name, v)| current.aux.get(*name) != Some(*v))
+            .filter_map(|(name, v)| aux_from_name(name).map(|dac| (dac, *v)))
+            .collect();
+        if !aux.is_empty() {
+            self._instrument.config_aux_channels(&aux).map_err(ArC2Error::new_exception)?;
+            for (dac, voltage) in &aux {
+                self._periph.aux[aux_index(*dac)] = Some(*voltage);
+            }
+        }
+
+        if let Some(selectors) = &target.selectors {
+            if current.selectors.as_ref() != Some(selectors) {
+                self._instrument.config_selectors(selectors).map_err(ArC2Error::new_exception)?;
+                self._periph.selectors = Some(selectors.clone());
+            }
+        }
+
+        let pairs = || current.channels.iter().zip(target.channels.iter()).enumerate();
+
+        // output ranges first so that voltages are programmed in the correct range
+        for (ext, range) in [(false, OutputRange::STD), (true, OutputRange::EXT)] {
+            let chans: Vec<usize> = pairs()
+                .filter(|(_, (cur, tgt))| tgt.ext_range == ext && cur.ext_range != ext)
+                .map(|(idx, _)| idx).collect();
+            if !chans.is_empty() {
+                self._instrument.set_channel_range(&chans, &range)
+                    .map_err(ArC2Error::new_exception)?;
+                self._channels.set_range(&chans, range);
+            }
+        }
+
+        // high speed channels cannot be put back into high speed mode without
+        // pulsing so they are restored as arbitrary voltage channels
+        let config: Vec<(u16, f32)> = pairs()
+            .filter(|(_, (cur, tgt))| tgt.mode != ChannelMode::Open &&
+                tgt.mode != ChannelMode::CurrentSource &&
+                (cur.mode != tgt.mode || cur.voltage != tgt.voltage))
+            .map(|(idx, (_, tgt))| (idx as u16, tgt.voltage)).collect();
+        if !config.is_empty() {
+            self._instrument.config_channels(&config, None).map_err(ArC2Error::new_exception)?;
+            self._channels.configure(&config, None);
+        }
+
+        let open: Vec<usize> = pairs()
+            .filter(|(_, (cur, tgt))| tgt.mode == ChannelMode::Open && cur.mode != ChannelMode::Open)
+            .map(|(idx, _)| idx).collect();
+        if !open.is_empty() {
+            self._instrument.open_channels(&open).map_err(ArC2Error::new_exception)?;
+            self._channels.open(&open);
+        }
+
+        let csrc: Vec<usize> = pairs()
+            .filter(|(_, (cur, tgt))| tgt.mode == ChannelMode::CurrentSource &&
+                cur.mode != ChannelMode::CurrentSource)
+            .map(|(idx, _)| idx).collect();
+        if !csrc.is_empty() {
+            self._instrument.connect_to_current_source(&csrc).map_err(ArC2Error::new_exception)?;
+            self._channels.current_source(&csrc);
+        }
+
+        if pairs().any(|(_, (cur, tgt))| cur.gnd != tgt.gnd) {
+            let gnd: Vec<usize> = target.channels.iter().enumerate()
+                .filter(|(_, tgt)| tgt.gnd).map(|(idx, _)| idx).collect();
+            self._instrument.connect_to_gnd(&gnd).map_err(ArC2Error::new_exception)?;
+            self._channels.set_gnd(&gnd, true, true);
+        }
+
+        if pairs().any(|(_, (cur, tgt))| cur.acgnd != tgt.acgnd) {
+            let acgnd: Vec<usize> = target.channels.iter().enumerate()
+                .filter(|(_, tgt)| tgt.acgnd).map(|(idx, _)| idx).collect();
+            self._instrument.connect_to_ac_gnd(&acgnd).map_err(ArC2Error::new_exception)?;
+            self._channels.set_acgnd(&acgnd, true, true);
+        }
+
+        Ok(())
+    }
+
+    /// Make sure ``level`` agrees with the logic level declared by the
+    /// connected peripheral, if any.
+    fn check_peripheral_level(&self, level: &LogicLevel) -> PyResult<()> {
+        if let Some(declared) = &self._gpio.peripheral_level {
+            if logic_level_name(declared) != logic_level_name(level) {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Logic level of {} V does not match the {} V declared by the \
+                        connected peripheral", logic_level_volts(level),
+                        logic_level_volts(declared))));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve named pins into GPIO numbers making sure they are outputs
+    fn output_pins(&self, states: &[(String, bool)]) -> PyResult<Vec<(usize, bool)>> {
+        let mut pins: Vec<(usize, bool)> = Vec::with_capacity(states.len());
+        for (name, state) in states {
+            let (pin, dir) = self._gpio.pin(name)?;
+            if dir != IODir::OUT {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("GPIO pin \"{}\" ({}) is an input and cannot be set", name, pin)));
+            }
+            pins.push((pin, *state));
+        }
+        Ok(pins)
+    }
+
+    /// Queue a GPIO update setting the output pins in ``states`` and retaining
+    /// the state of all other outputs.
+    fn queue_pins(&mut self, states: &[(usize, bool)]) -> PyResult<()> {
+
+        if let Some(level) = &self._periph.logic_level {
+            self.check_peripheral_level(level)?;
+        }
+
+        // clusters keep their last configured direction unless dictated by
+        // the named pins; the direction of a cluster that has never been
+        // configured is unknown and it cannot be reprogrammed blindly
+        let (mut mask, known) = match self._periph.logic {
+            Some((mask, dirs)) => (mask, dirs.map(Some)),
+            None => (0, [None; 4])
+        };
+
+        let mut dirs = [IODir::OUT; 4];
+        for (cluster, dir) in self._gpio.cluster_dirs().iter().enumerate() {
+            dirs[cluster] = match dir.or(known[cluster]) {
+                Some(d) => d,
+                None => return Err(exceptions::PyValueError::new_err(
+                    format!("Direction of cluster {} (GPIO {}-{}) is not known; name its \
+                        pins with define_pin or configure it with set_logic first",
+                        cluster, cluster*8, cluster*8 + 7)))
+            };
+        }
+
+        for (pin, state) in states {
+            let pin = *pin;
+            if pin >= 32 {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("GPIO {} is out of range (0-31)", pin)));
+            }
+            if dirs[pin / 8] != IODir::OUT {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("GPIO {} cannot be set as cluster {} (GPIO {}-{}) is an input",
+                        pin, pin / 8, (pin / 8) * 8, (pin / 8) * 8 + 7)));
+            }
+            if *state {
+                mask |= 1 << pin;
+            } else {
+                mask &= !(1 << pin);
+            }
+        }
+
+        self._instrument.set_logic(dirs[0], dirs[1], dirs[2], dirs[3], &IOMask::from_vals(&[mask]))
+            .map_err(ArC2Error::new_exception)?;
+        self._periph.logic = Some((mask, dirs));
+
+        Ok(())
+    }
+
+    /// The ``CSET`` voltage required to force ``amps`` with the configured
+    /// current source.
+    fn cset_for(&self, amps: f32) -> PyResult<f32> {
+        let (cref, resistor) = self._csrc.ok_or_else(||
+            exceptions::PyValueError::new_err(
+                "The current source has not been configured; use configure_current_source first"))?;
+
+        let delta = amps * resistor;
+        if delta.is_nan() || delta.abs() > CSRC_MAX_DELTA {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Current {} A is beyond the range of the current source (±{} A)",
+                    amps, CSRC_MAX_DELTA / resistor)));
+        }
+
+        Ok(cref + delta)
+    }
+
+    /// Queue the selector gating the device between ``low`` and ``high`` if
+    /// 1T1R mode is enabled. Returns the selectors that must be restored
+    /// afterwards or ``None`` if gating is disabled.
+    fn gate_select(&mut self, low: usize, high: usize) -> PyResult<Option<Vec<usize>>> {
+        if self._gate.voltage.is_none() {
+            return Ok(None);
+        }
+
+        let selector = match self._gate.map.get(&low).or_else(|| self._gate.map.get(&high)) {
+            Some(sel) => *sel,
+            None => {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("No selector is mapped to channel {} or {}", low, high)));
+            }
+        };
+
+        // SELH may have been reprogrammed since gating was enabled so the
+        // gate voltage is checked and asserted again if needed
+        let voltage = self._gate.voltage.unwrap();
+        self.check_limits("AUX DAC SELH", None, voltage)?;
+        if self._periph.aux[aux_index(AuxDACFn::SELH)] != Some(voltage) {
+            self._instrument.config_aux_channels(&[(AuxDACFn::SELH, voltage)])
+                .map_err(ArC2Error::new_exception)?;
+            self._periph.aux[aux_index(AuxDACFn::SELH)] = Some(voltage);
+        }
+
+        let previous = self._periph.selectors.clone().unwrap_or_default();
+        self._instrument.config_selectors(&[selector]).map_err(ArC2Error::new_exception)?;
+        self._periph.selectors = Some(vec![selector]);
+
+        Ok(Some(previous))
+    }
+
+    /// Queue the restoration of selectors saved by ``gate_select``.
+    fn gate_restore(&mut self, previous: Option<Vec<usize>>) -> PyResult<()> {
+        if let Some(selectors) = previous {
+            self._instrument.config_selectors(&selectors).map_err(ArC2Error::new_exception)?;
+            self._periph.selectors = Some(selectors);
+        }
+        Ok(())
+    }
+
+    /// Queue a sequence of open current reads on ``outs``, each one preceded
+    /// by the channel configuration of the corresponding step, and run them
+    /// in command buffers of up to ``BUFFER_READS`` steps. Channels not
+    /// included in a step are held at 0.0 V. Returns the currents of ``outs``
+    /// for every step.
+    fn open_read_sequence(&mut self, steps: &[Vec<(u16, f32)>], outs: &[usize])
+        -> PyResult<Vec<Vec<f32>>> {
+
+        check_channels(outs)?;
+        for step in steps {
+            let chans: Vec<usize> = step.iter().map(|(c, _)| *c as usize).collect();
+            check_channels(&chans)?;
+        }
+
+        for step in steps {
+            for (chan, voltage) in step {
+                self.check_voltage(*chan as usize, *voltage)?;
+            }
+        }
+        self.queue_ranges(steps.iter().flatten().map(|(chan, v)| (*chan as usize, *v)))?;
+
+        let mut results: Vec<Vec<f32>> = Vec::with_capacity(steps.len());
+
+        for chunk in steps.chunks(BUFFER_READS) {
+            for step in chunk {
+                self._instrument.config_channels(step, Some(0.0))
+                    .map_err(ArC2Error::new_exception)?;
+                self.record_applied(step.iter().map(|(chan, v)| (*chan as usize, *v)));
+                self._instrument.read_slice_open_deferred(outs, false)
+                    .map_err(ArC2Error::new_exception)?;
+            }
+
+            self._instrument.ground_all().map_err(ArC2Error::new_exception)?;
+            self._channels.ground_all();
+            self._instrument.execute().map_err(ArC2Error::new_exception)?;
+            self._instrument.wait();
+
+            results.extend(self.collect_reads(chunk.len(), outs)?);
+        }
+
+        Ok(results)
+    }
+
+    /// Queue a zero-bias reference read on ``chans``: all channels are held
+    /// at 0.0 V and the currents of ``chans`` are read.
+    fn queue_reference(&mut self, chans: &[usize]) -> PyResult<()> {
+        self._instrument.config_channels(&[], Some(0.0)).map_err(ArC2Error::new_exception)?;
+        self._instrument.read_slice_open_deferred(chans, false).map_err(ArC2Error::new_exception)?;
+        self._channels.configure(&[], Some(0.0));
+        Ok(())
+    }
+
+    /// Flush the command buffer returning ``chans`` to 0.0 V at the end,
+    /// same as the immediate read operations do
+    fn execute_soft_grounded(&mut self, chans: &[usize]) -> PyResult<()> {
+        let config: Vec<(u16, f32)> = chans.iter().map(|c| (*c as u16, 0.0)).collect();
+        self._instrument.config_channels(&config, None).map_err(ArC2Error::new_exception)?;
+        self._channels.soft_ground(chans);
+        self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        self._instrument.wait();
+        Ok(())
+    }
+
+    /// Run a read train in chunks of ``TRAIN_CHUNK_READS`` until the current
+    /// crosses ``threshold`` or ``max_reads`` reads have been done. Returns
+    /// the index of the crossing read, if any. Channels are left biased; the
+    /// caller must ground them whether this succeeds or not.
+    #[allow(clippy::too_many_arguments)]
+    fn current_train(&mut self, py: Python, low: usize, high: usize, vread: f32,
+        interpulse: u64, preload: Option<f32>, threshold: f32, above: bool,
+        max_reads: Option<usize>, baseline: bool) -> PyResult<Option<usize>> {
+
+        if baseline {
+            self.queue_reference(&[high])?;
+        }
+
+        if let Some(v) = preload {
+            self._instrument.config_channels(&[(high as u16, v)], None)
+                .map_err(ArC2Error::new_exception)?;
+            self.record_applied([(high, v)]);
+        }
+
+        let mut index: usize = 0;
+        let mut reference: Option<f32> = None;
+
+        let result = loop {
+            let chunk = match max_reads {
+                Some(max) => TRAIN_CHUNK_READS.min(max - index),
+                None => TRAIN_CHUNK_READS
+            };
+
+            if chunk == 0 {
+                break None;
+            }
+
+            self._instrument.generate_read_train(&[low], &[high], vread, chunk,
+                interpulse as u128, false).map_err(ArC2Error::new_exception)?;
+            self.record_applied([(low, -vread)]);
+            self._instrument.execute().map_err(ArC2Error::new_exception)?;
+            self._instrument.wait();
+
+            // every read in the chunk, including the reference that is
+            // queued with the first one, must be picked up, even after a
+            // crossing, so that the output buffer is left empty
+            let mut currents = self.collect_reads(
+                chunk + usize::from(baseline && reference.is_none()), &[high])?;
+            if baseline && reference.is_none() {
+                reference = Some(currents.remove(0)[0]);
+            }
+            let offset = reference.unwrap_or(0.0);
+
+            let crossing = currents.iter().position(|c| {
+                let current = (c[0] - offset).abs();
+                if above { current > threshold } else { current < threshold }
+            });
+
+            if let Some(idx) = crossing {
+                break Some(index + idx);
+            }
+
+            index += chunk;
+            py.check_signals()?;
+        };
+
+        Ok(result)
+    }
+
+    /// Flush the command buffer grounding all channels at the end
+    fn execute_grounded(&mut self) -> PyResult<()> {
+        self._instrument.ground_all().map_err(ArC2Error::new_exception)?;
+        self._channels.ground_all();
+        self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        self._instrument.wait();
+        Ok(())
+    }
+
+    /// Queue a single-crosspoint current read without flushing the command
+    /// buffer. The low channel is biased at ``-vread`` and the current is
+    /// read from the high channel, same as ``read_one``.
+    fn queue_read_one(&mut self, low: usize, high: usize, vread: f32) -> PyResult<()> {
+        self.check_voltage(low, vread)?;
+        self.queue_ranges([(low, vread)])?;
+        self._instrument.config_channels(&[(low as u16, -vread)], Some(0.0))
+            .map_err(ArC2Error::new_exception)?;
+        self.record_applied([(low, -vread)]);
+        self._instrument.read_slice_open_deferred(&[high], true)
+            .map_err(ArC2Error::new_exception)?;
+        self._channels.configure(&[], Some(0.0));
+        Ok(())
+    }
+
+    /// The calibration to apply to current read-outs, if any
+    fn active_calibration(&self) -> Option<&PyCalibration> {
+        if self._correction { self._calibration.as_ref() } else { None }
+    }
+
+    /// Correct currents read from ``chans``; ``values`` and ``chans`` are
+    /// matched by position. Calibrations are measured in the standard range
+    /// so channels in the extended range are not corrected.
+    fn correct(&self, chans: &[usize], values: &mut [f32]) {
+        if let Some(cal) = self.active_calibration() {
+            for (value, chan) in values.iter_mut().zip(chans.iter()) {
+                if *chan < 64 && self._channels.chans[*chan].range == OutputRange::STD {
+                    *value = cal.apply(*chan, *value);
+                }
+            }
+        }
+    }
+
+    /// Correct a 32×32 grid of currents as returned by ``read_all``. The
+    /// current of every crosspoint is read from its column (word line) when
+    /// rows are biased and from its row (bit line) otherwise.
+    fn correct_grid(&self, values: &mut [f32], rows_biased: bool) {
+        if self.active_calibration().is_none() {
+            return;
+        }
+        let bitlines = crossing_channels(16);
+        let wordlines = crossing_channels(0);
+        let chans: Vec<usize> = (0..values.len()).map(|idx| {
+            if rows_biased { wordlines[idx % 32] } else { bitlines[idx / 32] }
+        }).collect();
+        self.correct(&chans, values);
+    }
+
+    /// Retrieve ``count`` current read-outs from the output buffer keeping only
+    /// the values of channels ``chans``. Calibration is applied if enabled.
+    fn collect_reads(&mut self, count: usize, chans: &[usize]) -> PyResult<Vec<Vec<f32>>> {
+        let mut reads = self.collect(count, chans, ReadType::Current)?;
+        for read in reads.iter_mut() {
+            self.correct(chans, read);
+        }
+        Ok(reads)
+    }
+
+    /// Same as ``collect_reads`` but for voltage read-outs.
+    fn collect_voltages(&mut self, count: usize, chans: &[usize]) -> PyResult<Vec<Vec<f32>>> {
+        self.collect(count, chans, ReadType::Voltage)
+    }
+
+    fn collect(&mut self, count: usize, chans: &[usize], rtype: ReadType) -> PyResult<Vec<Vec<f32>>> {
+
+        check_channels(chans)?;
+
+        let mut results: Vec<Vec<f32>> = Vec::with_capacity(count);
+
+        while results.len() < count {
+            match self._instrument.pick_one(DataMode::All, rtype) {
+                Ok(Some(data)) => {
+                    results.push(chans.iter().map(|c| data[*c]).collect());
+                },
+                Ok(None) => {
+                    return Err(exceptions::PyRuntimeError::new_err(
+                        format!("Expected {} read-outs from the instrument but only {} \
+                            were available", count, results.len())));
+                },
+                Err(err) => return Err(ArC2Error::new_exception(err))
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
+#[pymethods]
+impl PyInstrument {
+
+    #[new]
+    #[pyo3(signature = (id, fw, init=None))]
+    fn new(id: i32, fw: &str, init: Option<bool>) -> PyResult<Self> {
+        let actual_init = match init {
+            Some(x) => x,
+            None => true
+        };
+        match Instrument::open_with_fw(id, fw, true, actual_init) {
+            Ok(instr) => Ok(PyInstrument {
+                _instrument: instr,
+                _limits: PySafetyLimits::default(),
+                _armed: false,
+                _exposure: HashMap::new(),
+                _channels: ChannelTracker::default(),
+                _periph: PeripheralState::default(),
+                _aux_presets: BTreeMap::new(),
+                _gpio: GpioState::default(),
+                _gate: GateState::default(),
+                _csrc: None,
+                _auto_range: false,
+                _range_switches: Vec::new(),
+                _applied: [None; 64],
+                _calibration: None,
+                _correction: true,
+                _port: id,
+                _firmware: fw.to_string()
+            }),
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// port(self, /)
+    /// --
+    ///
+    /// The EFM id this instrument was opened with.
+    ///
+    /// :rtype: int
+    fn port(&self) -> i32 {
+        self._port
+    }
+
+    /// firmware(self, /)
+    /// --
+    ///
+    /// The path of the firmware loaded on this instrument.
+    ///
+    /// :rtype: str
+    fn firmware(&self) -> String {
+        self._firmware.clone()
+    }
+
+    /// delay(self, nanos, /)
+    /// --
+    ///
+    /// Insert a delay of ``nanos`` nanoseconds in the command buffer.
+    fn delay<'py>(mut slf: PyRefMut<'py, Self>, nanos: u128) -> PyResult<PyRefMut<'py, Self>> {
+        match slf._instrument.add_delay(nanos) {
+            Ok(_) => Ok(slf),
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// ground_all(self, /)
+    /// --
+    ///
+    /// Ground all channels and revert them to arbitrary voltage operation.
+    fn ground_all<'py>(mut slf: PyRefMut<'py, Self>) -> PyResult<PyRefMut<'py, Self>> {
+        match slf._instrument.ground_all() {
+            Ok(_) => {
+                slf._channels.ground_all();
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// ground_all_fast(self, /)
+    /// --
+    ///
+    /// Ground all channels maintaing current channel operating mode.
+    fn ground_all_fast<'py>(mut slf: PyRefMut<'py, Self>) -> PyResult<PyRefMut<'py, Self>> {
+        match slf._instrument.ground_all_fast() {
+            Ok(_) => {
+                slf._channels.ground_all_fast();
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// connect_to_gnd(self, chans, /)
+    /// --
+    ///
+    /// Modify previously configured channels by switching them to ground. Use
+    /// an empty array to clear.
+    ///
+    /// :param chans: The channels to ground; this must be a numpy uint64 array or
+    ///               any Iterable whose elements can be converted to uint64.
+    fn connect_to_gnd<'py>(mut slf: PyRefMut<'py, Self>, chans: PyReadonlyArray1<'py, usize>)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        let slice = chans.as_slice().unwrap();
+        match slf._instrument.connect_to_gnd(slice) {
+            Ok(_) => {
+                slf._channels.set_gnd(slice, true, true);
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// gnd_add(self, chans, /)
+    /// --
+    ///
+    /// Connect selected channels to hard ground. Unlike
+    /// :meth:`~pyarc2.Instrument.connect_to_gnd` this function will not clear
+    /// previously grounded channels, only add to those.
+    fn gnd_add<'py>(mut slf: PyRefMut<'py, Self>, chans: PyReadonlyArray1<'py, usize>)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        let slice = chans.as_slice().unwrap();
+        match slf._instrument.gnd_add(slice) {
+            Ok(_) => {
+                slf._channels.set_gnd(slice, true, false);
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+
+    }
+
+    /// gnd_remove(self, chans, /)
+    /// --
+    ///
+    /// Disconnect selected channels from hard ground. Unlike
+    /// :meth:`~pyarc2.Instrument.connect_to_gnd` this function will not clear
+    /// previously grounded channels, only remove from those.
+    fn gnd_remove<'py>(mut slf: PyRefMut<'py, Self>, chans: PyReadonlyArray1<'py, usize>)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        let slice = chans.as_slice().unwrap();
+        match slf._instrument.gnd_remove(slice) {
+            Ok(_) => {
+                slf._channels.set_gnd(slice, false, false);
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+
+    }
+
+    /// connect_to_ac_gnd(self, chans, /)
+    /// --
     ///
     /// Modify previously configured channels by switching them to AC ground. Use
     /// an empty array to clear.
@@ -811,7 +3209,10 @@ impl PyInstrument {
 
         let slice = chans.as_slice().unwrap();
         match slf._instrument.connect_to_ac_gnd(slice) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf._channels.set_acgnd(slice, true, true);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
@@ -827,7 +3228,10 @@ impl PyInstrument {
 
         let slice = chans.as_slice().unwrap();
         match slf._instrument.gnd_ac_add(slice) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf._channels.set_acgnd(slice, true, false);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
 
@@ -844,7 +3248,10 @@ impl PyInstrument {
 
         let slice = chans.as_slice().unwrap();
         match slf._instrument.gnd_ac_remove(slice) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf._channels.set_acgnd(slice, false, false);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
 
@@ -856,7 +3263,10 @@ impl PyInstrument {
     /// Disconnect all channels.
     fn float_all<'py>(mut slf: PyRefMut<'py, Self>) -> PyResult<PyRefMut<'py, Self>> {
         match slf._instrument.float_all() {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf._channels.float_all();
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
@@ -879,7 +3289,10 @@ impl PyInstrument {
         PyResult<PyRefMut<'py, Self>> {
 
         match slf._instrument.open_channels(&channels) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf._channels.open(&channels);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
@@ -897,12 +3310,170 @@ impl PyInstrument {
     fn config_channels<'py>(mut slf: PyRefMut<'py, Self>, input: Vec<(u16, f32)>, base: Option<f32>)
         -> PyResult<PyRefMut<'py, Self>> {
 
+        for (chan, voltage) in &input {
+            slf.check_voltage(*chan as usize, *voltage)?;
+        }
+
+        let mut config: Vec<(usize, f32)> = input.iter()
+            .map(|(chan, v)| (*chan as usize, *v)).collect();
+        if let Some(v) = base {
+            for chan in 0..64usize {
+                if !input.iter().any(|(c, _)| *c as usize == chan) {
+                    slf.check_voltage(chan, v)?;
+                    config.push((chan, v));
+                }
+            }
+        }
+        slf.queue_ranges(config.iter().copied())?;
+
         match slf._instrument.config_channels(&input, base) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf._channels.configure(&input, base);
+                slf.record_applied(config);
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// config_channels_raw(self, config, base, /)
+    /// --
+    ///
+    /// Same as :meth:`~pyarc2.Instrument.config_channels` but with voltages
+    /// given as raw DAC codes which are sent to the instrument unmodified.
+    /// The voltage a code produces depends on the current output range of
+    /// every channel (see :meth:`~pyarc2.Instrument.dac_voltage`); it is
+    /// checked against the safety limits as usual. Output ranges are never
+    /// switched automatically as that would change the meaning of the codes.
+    ///
+    /// >>> # mid-scale is 0.0 V regardless of range
+    /// >>> arc.config_channels_raw([(3, 0x8000)], None).execute()
+    ///
+    /// :param config: An array of tuples ``[(channel, code), ...]`` specifying
+    ///                the DAC codes
+    /// :param base: DAC code to set all channels *not* included in ``config``.
+    ///              Set to ``None`` to leave them at their current state.
+    #[pyo3(signature = (input, base=None))]
+    fn config_channels_raw<'py>(mut slf: PyRefMut<'py, Self>, input: Vec<(u16, u16)>,
+        base: Option<u16>) -> PyResult<PyRefMut<'py, Self>> {
+
+        for (chan, _) in &input {
+            if *chan >= 64 {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Channel {} is out of range (0-63)", chan)));
+            }
+        }
+
+        // channels of different ranges produce a different voltage for the
+        // same code, so the base is checked on every individual channel
+        let mut config: Vec<(usize, f32)> = input.iter()
+            .map(|(chan, code)| (*chan as usize,
+                dac_voltage(*code, &slf._channels.chans[*chan as usize].range)))
+            .collect();
+        if let Some(code) = base {
+            for chan in 0..64usize {
+                if !input.iter().any(|(c, _)| *c as usize == chan) {
+                    config.push((chan, dac_voltage(code, &slf._channels.chans[chan].range)));
+                }
+            }
+        }
+
+        for (chan, voltage) in &config {
+            slf.check_limits(&format!("channel {}", chan), Some(*chan), *voltage)?;
+        }
+
+        // both halves of the channel DAC are set to the same code
+        let codes: Vec<(u16, u16, u16)> = input.iter()
+            .map(|(chan, code)| (*chan, *code, *code)).collect();
+
+        match slf._instrument.config_channels_raw(&codes, base.map(|code| (code, code))) {
+            Ok(_) => {
+                let voltages: Vec<(u16, f32)> = config.iter()
+                    .map(|(chan, v)| (*chan as u16, *v)).collect();
+                slf._channels.configure(&voltages, None);
+                slf.record_applied(config);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
 
+    /// quantise(self, voltage, range, /)
+    /// --
+    ///
+    /// The voltage actually produced by a channel DAC when programmed with
+    /// ``voltage`` in output range ``range``. The channel DACs are 16-bit
+    /// offset binary, with code ``0x8000`` at 0.0 V, so one code is
+    /// 20 V / 65536 (approximately 0.3 mV) in ``STD`` and 40 V / 65536
+    /// (approximately 0.6 mV) in ``EXT`` range. This is the nominal transfer
+    /// function of the DACs; it does not include gain or offset errors of
+    /// the output stage.
+    ///
+    /// >>> arc.quantise(0.2, OutputRange.STD)
+    /// 0.19989013671875
+    ///
+    /// :param float voltage: The requested voltage
+    /// :param range: A variant of :class:`~pyarc2.OutputRange`
+    /// :return: The quantised voltage
+    /// :rtype: float
+    fn quantise(&self, voltage: f32, range: PyOutputRange) -> f32 {
+        let range: OutputRange = range.into();
+        quantise(voltage, &range)
+    }
+
+    /// dac_code(self, voltage, range, /)
+    /// --
+    ///
+    /// The DAC code programmed for ``voltage`` in output range ``range`` (see
+    /// :meth:`~pyarc2.Instrument.quantise`). Voltages beyond the range are
+    /// clamped.
+    ///
+    /// >>> arc.dac_code(0.0, OutputRange.STD)
+    /// 32768
+    ///
+    /// :param float voltage: The requested voltage
+    /// :param range: A variant of :class:`~pyarc2.OutputRange`
+    /// :rtype: int
+    fn dac_code(&self, voltage: f32, range: PyOutputRange) -> u16 {
+        let range: OutputRange = range.into();
+        dac_code(voltage, &range)
+    }
+
+    /// dac_voltage(self, code, range, /)
+    /// --
+    ///
+    /// The voltage produced by DAC ``code`` in output range ``range``.
+    ///
+    /// :param int code: The DAC code
+    /// :param range: A variant of :class:`~pyarc2.OutputRange`
+    /// :rtype: float
+    fn dac_voltage(&self, code: u16, range: PyOutputRange) -> f32 {
+        let range: OutputRange = range.into();
+        dac_voltage(code, &range)
+    }
+
+    /// applied_voltages(self, /)
+    /// --
+    ///
+    /// The quantised value of the last voltage applied by the library on
+    /// every channel, including bias, pulse and read voltages. Channels that
+    /// have not been biased since the instrument was opened are omitted.
+    ///
+    /// Voltages are recorded once the operation applying them has been
+    /// queued successfully, with the sign they are applied with; reads for
+    /// instance bias the low channel at ``-vread``.
+    ///
+    /// >>> arc.pulse_one(3, 20, 1.5, 1000).execute()
+    /// >>> arc.applied_voltages()[20]
+    /// 1.49993896484375
+    ///
+    /// :return: A dict of ``{channel: voltage}``
+    fn applied_voltages(&self) -> BTreeMap<usize, f32> {
+        self._applied.iter().enumerate()
+            .filter_map(|(chan, v)| v.map(|v| (chan, v)))
+            .collect()
+    }
+
     /// config_aux_channels(self, config, base, /)
     /// --
     ///
@@ -923,10 +3494,146 @@ impl PyInstrument {
             }).collect();
 
         match slf._instrument.config_aux_channels(&rust_input) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                for (dac, voltage) in &rust_input {
+                    slf._periph.aux[aux_index(*dac)] = Some(*voltage);
+                }
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+
+    }
+
+    /// aux_channels(self, /)
+    /// --
+    ///
+    /// Get the last programmed voltage of every AUX DAC function. Functions
+    /// that have not been programmed through
+    /// :meth:`~pyarc2.Instrument.config_aux_channels` (or a preset) since the
+    /// instrument was opened are not included.
+    ///
+    /// >>> arc.config_aux_channels([(AuxDACFn.SELL, 0.0), (AuxDACFn.SELH, 3.3)])
+    /// >>> arc.aux_channels()
+    /// {'SELL': 0.0, 'SELH': 3.3}
+    ///
+    /// :return: A dict of ``{function name: voltage}`` where the names are
+    ///          those of the :class:`~pyarc2.AuxDACFn` attributes
+    fn aux_channels(&self) -> BTreeMap<String, f32> {
+        aux_by_name(&self._periph.aux_voltages())
+    }
+
+    /// save_aux_preset(self, name, voltages=None, /)
+    /// --
+    ///
+    /// Store a named AUX DAC preset. If ``voltages`` is ``None`` the last
+    /// programmed value of every AUX DAC function is stored, otherwise
+    /// ``voltages`` is stored as is, in the same form as the argument of
+    /// :meth:`~pyarc2.Instrument.config_aux_channels`. Saving a preset does
+    /// not program any DACs; use :meth:`~pyarc2.Instrument.apply_aux_preset`
+    /// for that. An existing preset with the same name is replaced.
+    ///
+    /// Presets are kept only for the lifetime of this instrument object and
+    /// are not persisted anywhere. To keep a preset across sessions store
+    /// the result of :meth:`~pyarc2.Instrument.aux_preset` (or save a
+    /// :class:`~pyarc2.Snapshot`, which includes the AUX DAC voltages) and
+    /// load it again with ``save_aux_preset`` in the new session.
+    ///
+    /// >>> stored = arc.aux_preset('1t1r')  # {'SELL': 0.0, ...}
+    /// >>> arc.save_aux_preset('1t1r', [(getattr(AuxDACFn, k), v)
+    /// >>>     for (k, v) in stored.items()])
+    ///
+    /// >>> # selector levels and gate supply of a 1T1R setup
+    /// >>> arc.save_aux_preset('1t1r', [(AuxDACFn.SELL, 0.0),
+    /// >>>     (AuxDACFn.SELH, 2.5), (AuxDACFn.ARB1, 1.2)])
+    /// >>> arc.apply_aux_preset('1t1r').execute()
+    ///
+    /// :param str name: The name of the preset
+    /// :param voltages: An array of tuples ``[(aux dac fn, voltage), ...]`` or
+    ///                  ``None`` to store the current values
+    /// :raises ValueError: If ``voltages`` is ``None`` and no AUX DAC has been
+    ///                     programmed yet
+    #[pyo3(signature = (name, voltages=None))]
+    fn save_aux_preset(&mut self, name: String, voltages: Option<Vec<(PyAuxDACFn, f32)>>)
+        -> PyResult<()> {
+
+        let preset: Vec<(AuxDACFn, f32)> = match voltages {
+            Some(v) => v.iter().map(|(dac, voltage)| (dac.into(), *voltage)).collect(),
+            None => self._periph.aux_voltages()
+        };
+
+        if preset.is_empty() {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Preset \"{}\" would be empty; no AUX DAC has been programmed", name)));
+        }
+
+        self._aux_presets.insert(name, preset);
+        Ok(())
+    }
+
+    /// apply_aux_preset(self, name, /)
+    /// --
+    ///
+    /// Queue the AUX DAC configuration of a named preset previously stored
+    /// with :meth:`~pyarc2.Instrument.save_aux_preset`. An
+    /// :meth:`~pyarc2.Instrument.execute` is required to actually load the
+    /// configuration.
+    ///
+    /// :param str name: The name of the preset
+    /// :raises KeyError: If no preset named ``name`` exists
+    fn apply_aux_preset<'py>(mut slf: PyRefMut<'py, Self>, name: &str)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        let preset = match slf._aux_presets.get(name) {
+            Some(p) => p.clone(),
+            None => return Err(exceptions::PyKeyError::new_err(
+                format!("No AUX DAC preset named \"{}\"", name)))
+        };
+
+        match slf._instrument.config_aux_channels(&preset) {
+            Ok(_) => {
+                for (dac, voltage) in &preset {
+                    slf._periph.aux[aux_index(*dac)] = Some(*voltage);
+                }
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
+    }
+
+    /// aux_preset(self, name, /)
+    /// --
+    ///
+    /// Get the contents of a named AUX DAC preset.
+    ///
+    /// :param str name: The name of the preset
+    /// :return: A dict of ``{function name: voltage}``
+    /// :raises KeyError: If no preset named ``name`` exists
+    fn aux_preset(&self, name: &str) -> PyResult<BTreeMap<String, f32>> {
+        match self._aux_presets.get(name) {
+            Some(preset) => Ok(aux_by_name(preset)),
+            None => Err(exceptions::PyKeyError::new_err(
+                format!("No AUX DAC preset named \"{}\"", name)))
+        }
+    }
+
+    /// aux_presets(self, /)
+    /// --
+    ///
+    /// Get the names of all stored AUX DAC presets in alphabetical order.
+    fn aux_presets(&self) -> Vec<String> {
+        self._aux_presets.keys().cloned().collect()
+    }
 
+    /// remove_aux_preset(self, name, /)
+    /// --
+    ///
+    /// Remove a named AUX DAC preset. Removing a preset that does not exist
+    /// is not an error.
+    ///
+    /// :param str name: The name of the preset
+    fn remove_aux_preset(&mut self, name: &str) {
+        self._aux_presets.remove(name);
     }
 
     /// config_selectors(self, selectors, /)
@@ -955,44 +3662,315 @@ impl PyInstrument {
         -> PyResult<PyRefMut<'py, Self>> {
 
         match slf._instrument.config_selectors(&selectors) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf._periph.selectors = Some(selectors);
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+
+    }
+
+    /// set_gate_map(self, mapping, /)
+    /// --
+    ///
+    /// Declare the selector that gates the access transistors of each
+    /// line for 1T1R operation. Typically the gates of a word line are tied
+    /// together and driven by a single selector, so ``mapping`` maps the
+    /// word line channels to selectors. When gating is enabled a single
+    /// device operation looks up the selector of its low channel first and
+    /// its high channel second. This replaces any previous mapping.
+    ///
+    /// >>> # word lines 16-31 are gated by selectors 0-15
+    /// >>> arc.set_gate_map({16 + i: i for i in range(16)})
+    ///
+    /// :param mapping: A dict of ``{channel: selector}``
+    /// :raises ValueError: If a channel or selector is out of range
+    fn set_gate_map(&mut self, mapping: BTreeMap<usize, usize>) -> PyResult<()> {
+        for (chan, sel) in &mapping {
+            if *chan >= 64 {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Channel {} is out of range (0-63)", chan)));
+            }
+            if *sel >= 32 {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Selector {} is out of range (0-31)", sel)));
+            }
+        }
+
+        self._gate.map = mapping;
+        Ok(())
+    }
+
+    /// gate_map(self, /)
+    /// --
+    ///
+    /// The mapping of channels to selectors used for 1T1R operation.
+    ///
+    /// :return: A dict of ``{channel: selector}``
+    fn gate_map(&self) -> BTreeMap<usize, usize> {
+        self._gate.map.clone()
+    }
+
+    /// enable_gating(self, voltage, /)
+    /// --
+    ///
+    /// Enable 1T1R operation. Reads and pulses on a single device
+    /// (:meth:`~pyarc2.Instrument.read_one`,
+    /// :meth:`~pyarc2.Instrument.pulse_one`,
+    /// :meth:`~pyarc2.Instrument.pulseread_one`) will raise the selector
+    /// gating the device, as declared with
+    /// :meth:`~pyarc2.Instrument.set_gate_map`, for the duration of the
+    /// operation and restore the previous selectors afterwards. The gate
+    /// voltage is applied through :meth:`~pyarc2.AuxDACFn.SELH` and acts as a
+    /// current compliance for the device; it is checked against the safety
+    /// limits and programmed again before a gated operation if ``SELH`` has
+    /// been changed in the meantime. Calling this again while gating is
+    /// enabled changes the gate voltage. Operations on complete rows,
+    /// columns or the whole array are not gated.
+    ///
+    /// >>> arc.enable_gating(1.2).execute()
+    /// >>> arc.pulse_one(3, 20, 2.0, 1000).execute()
+    ///
+    /// :param float voltage: The gate voltage
+    /// :raises ValueError: If no gate mapping has been declared
+    /// :raises SafetyLimitError: If ``voltage`` violates the safety limits
+    fn enable_gating<'py>(mut slf: PyRefMut<'py, Self>, voltage: f32)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        if slf._gate.map.is_empty() {
+            return Err(exceptions::PyValueError::new_err(
+                "No selectors have been mapped; use set_gate_map first"));
+        }
+        slf.check_limits("AUX DAC SELH", None, voltage)?;
+
+        match slf._instrument.config_aux_channels(&[(AuxDACFn::SELH, voltage)]) {
+            Ok(_) => {
+                slf._periph.aux[aux_index(AuxDACFn::SELH)] = Some(voltage);
+                slf._gate.voltage = Some(voltage);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
+    }
+
+    /// disable_gating(self, /)
+    /// --
+    ///
+    /// Disable 1T1R operation. Selectors and the ``SELH`` voltage are left as
+    /// they are.
+    fn disable_gating<'py>(mut slf: PyRefMut<'py, Self>) -> PyRefMut<'py, Self> {
+        slf._gate.voltage = None;
+        slf
+    }
+
+    /// gate_voltage(self, /)
+    /// --
+    ///
+    /// The gate voltage used in 1T1R operation.
+    ///
+    /// :return: The gate voltage or ``None`` if gating is disabled
+    fn gate_voltage(&self) -> Option<f32> {
+        self._gate.voltage
+    }
+
+    /// gate_ramp(self, low, high, vread, vstart, vstep, vstop, vpulse=None, nanos=None, /)
+    /// --
+    ///
+    /// Sweep the gate voltage of a 1T1R device and read its current at every
+    /// step. If ``vpulse`` and ``nanos`` are provided the device is pulsed
+    /// before each read, which allows programming with a stepped compliance.
+    /// All steps run in a single command buffer. The selector of the device
+    /// is taken from the gate map; gating need not be enabled. Afterwards the
+    /// previous selectors and gate voltage are restored.
+    ///
+    /// >>> # transfer curve at 0.2 V from 0 to 2 V of gate voltage
+    /// >>> currents = arc.gate_ramp(3, 20, 0.2, 0.0, 0.05, 2.0)
+    ///
+    /// :param int low: The low voltage channel
+    /// :param int high: The high voltage channel
+    /// :param float vread: The voltage to read at
+    /// :param float vstart: The first gate voltage
+    /// :param float vstep: The gate voltage step
+    /// :param float vstop: The last gate voltage
+    /// :param float vpulse: The pulsing voltage or ``None`` to only read
+    /// :param int nanos: The pulse width in nanoseconds
+    /// :return: The current of the device at every gate voltage step
+    /// :rtype: A numpy f32 array
+    /// :raises ValueError: If the device has no selector mapped, the step
+    ///                     is invalid or only one of ``vpulse`` and ``nanos``
+    ///                     is provided
+    #[pyo3(signature = (low, high, vread, vstart, vstep, vstop, vpulse=None, nanos=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn gate_ramp<'py>(&mut self, py: Python<'py>, low: usize, high: usize, vread: f32,
+        vstart: f32, vstep: f32, vstop: f32, vpulse: Option<f32>, nanos: Option<u128>)
+        -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
+
+        if vstep == 0.0 || vstep.is_nan() || (vstop - vstart) * vstep < 0.0 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Cannot reach {} V from {} V in steps of {} V", vstop, vstart, vstep)));
+        }
+
+        let pulse = match (vpulse, nanos) {
+            (Some(v), Some(n)) => Some((v, n)),
+            (None, None) => None,
+            _ => {
+                return Err(exceptions::PyValueError::new_err(
+                    "Both vpulse and nanos must be provided to pulse before reading"));
+            }
+        };
+
+        let nsteps = ramp_steps(vstart, vstep, vstop);
+        let voltages: Vec<f32> = (0..nsteps).map(|i| ramp_value(vstart, vstep, i)).collect();
+        for vgate in &voltages {
+            self.check_limits("AUX DAC SELH", None, *vgate)?;
+        }
+
+        let exposure = match pulse {
+            Some((v, n)) => self.check_pulses(&[high], &[device_key(low, high)], v, n, nsteps as u64)?,
+            None => PendingExposure::default()
+        };
+        self.check_voltage(low, vread)?;
+
+        let selector = match self._gate.map.get(&low).or_else(|| self._gate.map.get(&high)) {
+            Some(sel) => *sel,
+            None => {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("No selector is mapped to channel {} or {}", low, high)));
+            }
+        };
+
+        let previous_sels = self._periph.selectors.clone().unwrap_or_default();
+        let previous_selh = self._periph.aux[aux_index(AuxDACFn::SELH)];
+
+        if let Some((v, _)) = pulse {
+            self.queue_ranges([(high, v)])?;
+        }
+        self._instrument.config_selectors(&[selector]).map_err(ArC2Error::new_exception)?;
+
+        for vgate in &voltages {
+            self._instrument.config_aux_channels(&[(AuxDACFn::SELH, *vgate)])
+                .map_err(ArC2Error::new_exception)?;
+            if let Some((v, n)) = pulse {
+                self._instrument.pulse_one(low, high, v, n).map_err(ArC2Error::new_exception)?;
+            }
+            self.queue_read_one(low, high, vread)?;
+        }
+        self.charge(exposure);
+        if let Some((v, _)) = pulse {
+            self.record_applied([(high, v)]);
+        }
+
+        self._instrument.config_selectors(&previous_sels).map_err(ArC2Error::new_exception)?;
+        self._periph.selectors = Some(previous_sels);
+        match previous_selh {
+            Some(v) => {
+                self._instrument.config_aux_channels(&[(AuxDACFn::SELH, v)])
+                    .map_err(ArC2Error::new_exception)?;
+            },
+            None => {
+                self._periph.aux[aux_index(AuxDACFn::SELH)] = voltages.last().copied();
+            }
+        }
+
+        self._instrument.ground_all().map_err(ArC2Error::new_exception)?;
+        self._channels.ground_all();
+        self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        self._instrument.wait();
+
+        let currents: Vec<f32> = self.collect_reads(nsteps, &[high])?
+            .into_iter().map(|r| r[0]).collect();
 
+        Ok(currents.into_pyarray_bound(py))
     }
 
-    /// read_one(self, low, high, vread, /)
+    /// read_one(self, low, high, vread, baseline=False, /)
     /// --
     ///
     /// Perform a current read between the specified channels. The low
     /// channel will be biased with `-vread` and the current will be read
-    /// from the high channel.
+    /// from the high channel. In 1T1R mode (see
+    /// :meth:`~pyarc2.Instrument.enable_gating`) the selector of the device
+    /// is raised for the duration of the read.
+    ///
+    /// With ``baseline`` a reference read with all channels at 0.0 V is
+    /// taken right before the read, in the same command buffer, and
+    /// subtracted from the result. This removes leakage and amplifier
+    /// offsets that dominate when reading high resistance states.
     ///
     /// :param int low: The low voltage channel
     /// :param int high: The high voltage channel
     /// :param float vread: The voltage to read at
+    /// :param bool baseline: Subtract a zero-bias reference read
     /// :return: The current between the specified crosspoints at ``vread``
     /// :rtype: float
-    fn read_one(&mut self, low: usize, high: usize, vread: f32) -> f32 {
-        self._instrument.read_one(low, high, vread).unwrap()
+    #[pyo3(signature = (low, high, vread, baseline=false))]
+    fn read_one(&mut self, low: usize, high: usize, vread: f32, baseline: bool) -> PyResult<f32> {
+        self.check_voltage(low, vread)?;
+        let gated = self.gate_select(low, high)?;
+        self.queue_ranges([(low, vread)])?;
+
+        if baseline {
+            self.queue_reference(&[high])?;
+            self.queue_read_one(low, high, vread)?;
+            self.gate_restore(gated)?;
+            self.execute_soft_grounded(&[low, high])?;
+            let reads = self.collect_reads(2, &[high])?;
+            return Ok(reads[1][0] - reads[0][0]);
+        }
+
+        let mut res = self._instrument.read_one(low, high, vread).map_err(ArC2Error::new_exception)?;
+        self.record_applied([(low, -vread)]);
+        self.correct(&[high], std::slice::from_mut(&mut res));
+        self._channels.soft_ground(&[low, high]);
+        if gated.is_some() {
+            self.gate_restore(gated)?;
+            self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        }
+        Ok(res)
     }
 
-    /// read_slice(self, chan, vread, /)
+    /// read_slice(self, chan, vread, baseline=False, /)
     /// --
     ///
     /// Read all the values which have ``chan`` as the low channel. If ``chan`` is
     /// between 0 and 15 or 32 and 47 (inclusive) this will correspond to a
     /// row read at ``vread`` in a standard 32×32 array. Otherwise it's a column
-    /// read.
+    /// read. See :meth:`~pyarc2.Instrument.read_one` for ``baseline``.
     ///
     /// :param int chan: The low voltage channel
     /// :param float vread: The voltage to read at
+    /// :param bool baseline: Subtract a zero-bias reference read
     /// :return: The current of each individual channel along the ``chan`` line sinked
     ///          at ``chan``
     /// :rtype: A numpy f32 array
-    fn read_slice<'py>(&mut self, py: Python<'py>, chan: usize, vread: f32) -> Bound<'py, PyArray<f32, Ix1>> {
-        let array = self._instrument.read_slice(chan, vread).unwrap();
-        array.into_pyarray_bound(py)
+    #[pyo3(signature = (chan, vread, baseline=false))]
+    fn read_slice<'py>(&mut self, py: Python<'py>, chan: usize, vread: f32, baseline: bool)
+        -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
+        self.check_voltage(chan, vread)?;
+        self.queue_ranges([(chan, vread)])?;
+
+        if baseline {
+            let highs = crossing_channels(chan);
+            self.queue_reference(&highs)?;
+            self._instrument.config_channels(&[(chan as u16, -vread)], Some(0.0))
+                .map_err(ArC2Error::new_exception)?;
+            self._instrument.read_slice_open_deferred(&highs, false)
+                .map_err(ArC2Error::new_exception)?;
+            self.record_applied([(chan, -vread)]);
+            self.execute_soft_grounded(&highs.iter().copied().chain([chan]).collect::<Vec<_>>())?;
+            let reads = self.collect_reads(2, &highs)?;
+            let currents: Vec<f32> = reads[1].iter().zip(reads[0].iter())
+                .map(|(i, i0)| i - i0).collect();
+            return Ok(currents.into_pyarray_bound(py));
+        }
+
+        let mut array = self._instrument.read_slice(chan, vread).map_err(ArC2Error::new_exception)?;
+        self.record_applied([(chan, -vread)]);
+        self.correct(&crossing_channels(chan), &mut array);
+        self._channels.soft_ground(&crossing_channels(chan));
+        self._channels.soft_ground(&[chan]);
+        Ok(array.into_pyarray_bound(py))
     }
 
     /// read_slice_masked(self, chan, mask, vread, /)
@@ -1011,12 +3989,19 @@ impl PyInstrument {
     ///          at ``chan``; unselected channels will default to ``NaN``
     /// :rtype: A numpy f32 array
     fn read_slice_masked<'py>(&mut self, py: Python<'py>, chan: usize,
-        mask: PyReadonlyArray1<'py, usize>, vread: f32) -> Bound<'py, PyArray<f32, Ix1>> {
+        mask: PyReadonlyArray1<'py, usize>, vread: f32) -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
 
+        self.check_voltage(chan, vread)?;
+        self.queue_ranges([(chan, vread)])?;
         let maskslice = mask.as_slice().unwrap();
-        let res = self._instrument.read_slice_masked(chan, maskslice, vread).unwrap();
-
-        res.into_pyarray_bound(py)
+        let mut res = self._instrument.read_slice_masked(chan, maskslice, vread)
+            .map_err(ArC2Error::new_exception)?;
+        self.record_applied([(chan, -vread)]);
+        self.correct(&crossing_channels(chan), &mut res);
+        self._channels.soft_ground(maskslice);
+        self._channels.soft_ground(&[chan]);
+
+        Ok(res.into_pyarray_bound(py))
     }
 
     /// mac(self, inp_chans, out_chans, /)
@@ -1031,30 +4016,267 @@ impl PyInstrument {
     /// :return: The current of each individual channel along the ``out_chans`` line
     /// :rtype: A numpy f32 array
     fn mac<'py>(&mut self, py: Python<'py>, inp_chans: Vec<(usize, f32)>,
-        out_chans: Vec<usize>) -> Bound<'py, PyArray<f32, Ix1>> {
+        out_chans: Vec<usize>) -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
+
+        self.check_voltages(&inp_chans)?;
+        self.queue_ranges(inp_chans.iter().copied())?;
+        let mut res = self._instrument.mac(&inp_chans, &out_chans).map_err(ArC2Error::new_exception)?;
+        self.record_applied(inp_chans.iter().copied());
+        self.correct(&out_chans, &mut res);
+        self._channels.soft_ground(&inp_chans.iter().map(|(c, _)| *c).collect::<Vec<usize>>());
+        self._channels.soft_ground(&out_chans);
+
+        Ok(res.into_pyarray_bound(py))
+    }
+
+    /// mac_encoded(self, inp_chans, out_chans, vread, encoding, /)
+    /// --
+    ///
+    /// Multiply-accumulate with digitally encoded multi-bit inputs. Unlike
+    /// :meth:`~pyarc2.Instrument.mac`, which maps inputs to analogue voltages,
+    /// every input channel is driven either at ``vread`` or at 0.0 V for a
+    /// number of read steps dictated by ``encoding`` (see
+    /// :class:`~pyarc2.MACEncoding`). All steps are queued and executed as a
+    /// single command buffer, so that inputs are applied back to back, and
+    /// the currents of each output channel are accumulated across steps. As
+    /// every step stores one read-out the encoding may not have more steps
+    /// than fit in the output memory of the instrument (256).
+    ///
+    /// >>> # 4-bit inputs applied on channels 0 and 1
+    /// >>> res = arc.mac_encoded([(0, 5), (1, 12)], [16, 17], 0.2,
+    /// >>>     MACEncoding.BitSerial(4))
+    ///
+    /// :param list inp_chans: A list of tuples ``(chan number, input value)``
+    ///                        where input values are unsigned integers
+    /// :param out_chans: An array of uint64s or any Iterable with elements that
+    ///                   can be converted into uint64
+    /// :param float vread: The read-out voltage of a single step
+    /// :param encoding: A variant of :class:`pyarc2.MACEncoding`
+    /// :return: The accumulated current of each channel in ``out_chans``
+    /// :rtype: A numpy f32 array
+    /// :raises ValueError: If a channel is out of range, an input value
+    ///                     cannot be represented with ``encoding`` or
+    ///                     ``encoding`` has more than 256 steps
+    fn mac_encoded<'py>(&mut self, py: Python<'py>, inp_chans: Vec<(usize, u32)>,
+        out_chans: Vec<usize>, vread: f32, encoding: PyMACEncoding)
+        -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
+
+        let encoding = encoding._inner;
+        let max_value = encoding.max_value();
+
+        check_channels(inp_chans.iter().map(|(c, _)| c).chain(out_chans.iter()))?;
+
+        for (chan, val) in &inp_chans {
+            if *val > max_value {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Input value {} on channel {} exceeds the maximum of {} \
+                        for this encoding", val, chan, max_value)));
+            }
+        }
+
+        let count = encoding.step_count();
+        if count as usize > BUFFER_READS {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Encoding needs {} read steps but a single command buffer holds at \
+                    most {}", count, BUFFER_READS)));
+        }
+
+        let (steps, weights): (Vec<Vec<(u16, f32)>>, Vec<f32>) = (0..count)
+            .map(|k| encoding.step(k, &inp_chans, vread)).unzip();
+
+        let currents = self.open_read_sequence(&steps, &out_chans)?;
+
+        let mut result = vec![0.0f32; out_chans.len()];
+        for (row, weight) in currents.iter().zip(weights.iter()) {
+            for (acc, current) in result.iter_mut().zip(row.iter()) {
+                *acc += current * weight;
+            }
+        }
+
+        Ok(result.into_pyarray_bound(py))
+    }
+
+    /// mvm(self, inputs, weights_map, v_scale, vmax=None, /)
+    /// --
+    ///
+    /// Perform a batched analogue matrix-vector multiplication on the weights
+    /// stored in the array. Each row of ``inputs`` is converted to voltages
+    /// (``v_scale`` volts per unit input) and applied to the input channels of
+    /// ``weights_map`` while the currents of the output channels are read. The
+    /// whole batch is queued and executed as a single command buffer; as
+    /// every row stores one read-out a batch can have at most 256 rows, the
+    /// number of read-outs that fit in the output memory. Currents
+    /// are scaled back to the numeric domain by dividing by ``v_scale`` and
+    /// the ``g_unit`` of the map; for differential maps every output is the
+    /// difference of its positive and negative column.
+    ///
+    /// >>> wmap = WeightMap.differential([0, 1, 2], [(16, 17), (18, 19)], 1e-4)
+    /// >>> x = np.array([[0.1, 0.5, 1.0], [1.0, 0.0, -1.0]], dtype=np.float32)
+    /// >>> y = arc.mvm(x, wmap, 0.2) # y.shape == (2, 2)
+    ///
+    /// :param inputs: A 2D numpy f32 array with shape ``(batch, n)`` where ``n``
+    ///                is the number of input channels of ``weights_map``
+    /// :param weights_map: A :class:`~pyarc2.WeightMap` with the channel layout
+    /// :param float v_scale: Volts applied per unit of input value
+    /// :param float vmax: Maximum absolute input voltage; defaults to 0.5 V
+    /// :return: The output values with shape ``(batch, m)`` where ``m`` is the
+    ///          number of outputs of ``weights_map``
+    /// :rtype: A numpy (batch, m) f32 ndarray
+    /// :raises ValueError: If the input dimensions do not match the map, the
+    ///                     batch has more than 256 rows or an input would
+    ///                     exceed ``vmax``
+    #[pyo3(signature = (inputs, weights_map, v_scale, vmax=None))]
+    fn mvm<'py>(&mut self, py: Python<'py>, inputs: PyReadonlyArray2<'py, f32>,
+        weights_map: PyWeightMap, v_scale: f32, vmax: Option<f32>)
+        -> PyResult<Bound<'py, PyArray<f32, Ix2>>> {
+
+        let vmax = vmax.unwrap_or(MVM_DEFAULT_VMAX).abs();
+        let inputs = inputs.as_array();
+        let (batch, ninputs) = inputs.dim();
+
+        if ninputs != weights_map.inputs.len() {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Input has {} columns but the weight map has {} input channels",
+                    ninputs, weights_map.inputs.len())));
+        }
+
+        if batch > BUFFER_READS {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Batch has {} rows but a single command buffer holds at most {}; \
+                    split it into smaller batches", batch, BUFFER_READS)));
+        }
+
+        if v_scale.is_nan() || v_scale <= 0.0 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("v_scale must be positive, got {}", v_scale)));
+        }
+
+        let mut steps: Vec<Vec<(u16, f32)>> = Vec::with_capacity(batch);
+
+        for (b, row) in inputs.outer_iter().enumerate() {
+            let mut step: Vec<(u16, f32)> = Vec::with_capacity(ninputs);
+            for (i, (x, chan)) in row.iter().zip(weights_map.inputs.iter()).enumerate() {
+                let voltage = x * v_scale;
+                if voltage.abs() > vmax {
+                    return Err(exceptions::PyValueError::new_err(
+                        format!("Input [{}, {}] = {} maps to {} V which exceeds the \
+                            read limit of {} V; reduce v_scale", b, i, x, voltage, vmax)));
+                }
+                step.push((*chan as u16, voltage));
+            }
+            steps.push(step);
+        }
 
-        let res = self._instrument.mac(&inp_chans, &out_chans).unwrap();
+        let outs = weights_map.read_channels();
+        let currents = self.open_read_sequence(&steps, &outs)?;
 
-        res.into_pyarray_bound(py)
+        let scale = v_scale * weights_map.g_unit;
+        let current_of = |row: &[f32], chan: usize| -> f32 {
+            // outs is sorted so a binary search is enough
+            row[outs.binary_search(&chan).unwrap()]
+        };
+
+        let mut values: Vec<f32> = Vec::with_capacity(batch * weights_map.outputs.len());
+        for row in &currents {
+            for (pos, neg) in &weights_map.outputs {
+                let current = match neg {
+                    Some(n) => current_of(row, *pos) - current_of(row, *n),
+                    None => current_of(row, *pos)
+                };
+                values.push(current / scale);
+            }
+        }
+
+        let result = Array2::from_shape_vec((batch, weights_map.outputs.len()), values)
+            .map_err(|err| exceptions::PyRuntimeError::new_err(format!("{}", err)))?;
+
+        Ok(result.into_pyarray_bound(py))
     }
 
-    /// read_all(self, vread, order, /)
+    /// read_all(self, vread, order, baseline=False, /)
     /// --
     ///
     /// Read all the available crosspoints at the specified voltage. This can be
-    /// done by biasing either rows or columns.
+    /// done by biasing either rows or columns. See
+    /// :meth:`~pyarc2.Instrument.read_one` for ``baseline``; a single
+    /// reference read of all channels is used for the whole array.
     ///
     /// :param float vread: The read-out voltage
     /// :param order: A variant of :class:`pyarc2.BiasOrder` denoting which rows are
     ///              biased during read-out.
+    /// :param bool baseline: Subtract a zero-bias reference read
     /// :return: An 32×32 array containing the current measured on each individual
     ///          cronsspoint
     /// :rtype: A numpy (2, 2) f32 ndarray
-    fn read_all<'py>(&mut self, py: Python<'py>, vread: f32, order: PyBiasOrder) -> Bound<'py, PyArray<f32, Ix2>> {
+    #[pyo3(signature = (vread, order, baseline=false))]
+    fn read_all<'py>(&mut self, py: Python<'py>, vread: f32, order: PyBiasOrder, baseline: bool)
+        -> PyResult<Bound<'py, PyArray<f32, Ix2>>> {
+
+        for chan in 0..64usize {
+            self.check_voltage(chan, vread)?;
+        }
+        self.queue_ranges((0..64usize).map(|chan| (chan, vread)))?;
+        let rows_biased = matches!(order._inner, BiasOrder::Rows);
+
+        if baseline {
+            let all: Vec<usize> = (0..64).collect();
+            let bitlines = crossing_channels(16);
+            let wordlines = crossing_channels(0);
+            let (biased, sensed) = if rows_biased { (&bitlines, &wordlines) } else { (&wordlines, &bitlines) };
+
+            self.queue_reference(&all)?;
+            for line in biased {
+                self._instrument.config_channels(&[(*line as u16, -vread)], Some(0.0))
+                    .map_err(ArC2Error::new_exception)?;
+                self._instrument.read_slice_open_deferred(sensed, false)
+                    .map_err(ArC2Error::new_exception)?;
+            }
+            self.record_applied(biased.iter().map(|line| (*line, -vread)));
+            self.execute_grounded()?;
+
+            let reference = self.collect_reads(1, sensed)?.remove(0);
+            let lines = self.collect_reads(32, sensed)?;
+
+            // lines are indexed by the biased line so columns biasing needs
+            // to be transposed into the row-major grid
+            let mut data = vec![0.0f32; 1024];
+            for (l, line) in lines.iter().enumerate() {
+                for (s, current) in line.iter().enumerate() {
+                    let idx = if rows_biased { l * 32 + s } else { s * 32 + l };
+                    data[idx] = current - reference[s];
+                }
+            }
 
-        let data = self._instrument.read_all(vread, order.into()).unwrap();
+            let array = data.into_pyarray_bound(py);
+            return Ok(array.borrow().reshape((32, 32)).unwrap());
+        }
+
+        let mut data = self._instrument.read_all(vread, order.into()).map_err(ArC2Error::new_exception)?;
+        let biased = crossing_channels(if rows_biased { 16 } else { 0 });
+        self.record_applied(biased.iter().map(|line| (*line, -vread)));
+        self.correct_grid(&mut data, rows_biased);
+        self._channels.ground_all();
         let array = data.into_pyarray_bound(py);
-        array.borrow().reshape((32, 32)).unwrap()
+        Ok(array.borrow().reshape((32, 32)).unwrap())
+    }
+
+    /// read_one_deferred(self, low, high, vread, /)
+    /// --
+    ///
+    /// Queue a current read between the specified channels without flushing the
+    /// command buffer. Same semantics as :meth:`~pyarc2.Instrument.read_one`
+    /// apply. This can be used to interleave read-outs with pulses, delays and
+    /// GPIO operations in a single :meth:`~pyarc2.Instrument.execute`. Results
+    /// must be retrieved with :meth:`~pyarc2.Instrument.pick_one`; the current
+    /// is found at index ``high`` of a ``DataMode.All`` read-out.
+    ///
+    /// :param int low: The low voltage channel
+    /// :param int high: The high voltage channel
+    /// :param float vread: The voltage to read at
+    fn read_one_deferred<'py>(mut slf: PyRefMut<'py, Self>, low: usize, high: usize, vread: f32)
+        -> PyResult<PyRefMut<'py, Self>> {
+        slf.queue_read_one(low, high, vread)?;
+        Ok(slf)
     }
 
     /// read_slice_open_deferred(self, highs, ground_after, /)
@@ -1071,7 +4293,10 @@ impl PyInstrument {
         let ground = ground_after.unwrap_or(true);
 
         match slf._instrument.read_slice_open_deferred(slice, ground) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                if ground { slf._channels.soft_ground(slice); }
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
 
@@ -1097,7 +4322,13 @@ impl PyInstrument {
         let slice = highs.as_slice().unwrap();
         let ground = ground_after.unwrap_or(true);
 
-        self._instrument.read_slice_open(slice, ground).unwrap().into_pyarray_bound(py)
+        let mut res = self._instrument.read_slice_open(slice, ground).unwrap();
+        let chans: Vec<usize> = (0..res.len()).collect();
+        self.correct(&chans, &mut res);
+        if ground {
+            self._channels.soft_ground(slice);
+        }
+        res.into_pyarray_bound(py)
     }
 
     /// pulse_one(self, low, high, voltage, nanos, /)
@@ -1113,8 +4344,18 @@ impl PyInstrument {
     fn pulse_one<'py>(mut slf: PyRefMut<'py, Self>, low: usize, high: usize, voltage: f32, nanos: u128)
         -> PyResult<PyRefMut<'py, Self>> {
 
+        let exposure = slf.check_pulses(&[high], &[device_key(low, high)], voltage, nanos, 1)?;
+        let gated = slf.gate_select(low, high)?;
+        slf.queue_ranges([(high, voltage)])?;
+
         match slf._instrument.pulse_one(low, high, voltage, nanos) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf.charge(exposure);
+                slf.record_applied([(high, voltage)]);
+                slf._channels.soft_ground(&[low, high]);
+                slf.gate_restore(gated)?;
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
@@ -1131,8 +4372,19 @@ impl PyInstrument {
     fn pulse_slice<'py>(mut slf: PyRefMut<'py, Self>, chan: usize, voltage: f32, nanos: u128)
         -> PyResult<PyRefMut<'py, Self>> {
 
+        let highs = crossing_channels(chan);
+        let devices: Vec<(usize, usize)> = highs.iter().map(|h| device_key(chan, *h)).collect();
+        let exposure = slf.check_pulses(&highs, &devices, voltage, nanos, 1)?;
+        slf.queue_ranges(highs.iter().map(|h| (*h, voltage)))?;
+
         match slf._instrument.pulse_slice(chan, voltage, nanos) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf.charge(exposure);
+                slf.record_applied(highs.iter().map(|h| (*h, voltage)));
+                slf._channels.soft_ground(&highs);
+                slf._channels.soft_ground(&[chan]);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
@@ -1154,9 +4406,18 @@ impl PyInstrument {
         -> PyResult<PyRefMut<'py, Self>> {
 
         let actual_mask = mask.as_slice().unwrap();
+        let devices: Vec<(usize, usize)> = actual_mask.iter().map(|h| device_key(chan, *h)).collect();
+        let exposure = slf.check_pulses(actual_mask, &devices, voltage, nanos, 1)?;
+        slf.queue_ranges(actual_mask.iter().map(|h| (*h, voltage)))?;
 
         match slf._instrument.pulse_slice_masked(chan, actual_mask, voltage, nanos) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf.charge(exposure);
+                slf.record_applied(actual_mask.iter().map(|h| (*h, voltage)));
+                slf._channels.soft_ground(actual_mask);
+                slf._channels.soft_ground(&[chan]);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
 
@@ -1201,17 +4462,234 @@ impl PyInstrument {
         cl_nanos: Vec<Option<u128>>, preset_state: bool) -> PyResult<PyRefMut<'py, Self>> {
 
         if cl_nanos.len() != 8 {
-            return Err(exceptions::PyValueError::new_err("Need 8 arguments for cluster timings"));
+            return Err(exceptions::PyValueError::new_err(
+                format!("cl_nanos must contain exactly 8 cluster timings, one for every \
+                    8 consecutive channels, but {} were provided; use None for clusters \
+                    that should not be pulsed", cl_nanos.len())));
         }
 
         let actual_cl_nanos: [Option<u128>; 8] = cl_nanos[0..8].try_into()?;
+        let exposure = slf.check_open_pulses(&chans, &actual_cl_nanos)?;
+        slf.queue_ranges(open_pulse_voltages(&chans, &actual_cl_nanos))?;
 
         match slf._instrument.pulse_slice_fast_open(&chans, &actual_cl_nanos, preset_state) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf.charge(exposure);
+                slf.record_applied(open_pulse_voltages(&chans, &actual_cl_nanos));
+                slf._channels.high_speed(&chans);
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// fast_pulse(self, channels, normal=0.0, preset_state=True, /)
+    /// --
+    ///
+    /// Apply sub-500 ms pulses on arbitrary channels using the high speed
+    /// drivers. This is a higher level interface to
+    /// :meth:`~pyarc2.Instrument.pulse_slice_fast_open` that works out the
+    /// cluster timings from the requested pulse widths. ``channels`` is a
+    /// dict mapping channel numbers to ``(pulse voltage, pulse width)``.
+    /// The 64 channels are organised in 8 clusters of 8 consecutive channels
+    /// (channels 0–7 are cluster 0, 8–15 cluster 1 and so on) and all pulsed
+    /// channels of a cluster must share the same pulse width and the same
+    /// direction of transition from ``normal`` to pulse voltage.
+    ///
+    /// >>> # 100 ns pulses on channels 3 and 5 and a 1 μs pulse on channel 12
+    /// >>> arc.fast_pulse({3: (1.5, 100), 5: (1.2, 100), 12: (-2.0, 1000)})
+    /// >>>    .execute()
+    ///
+    /// :param dict channels: A dict of ``{chan: (pulse voltage, pulse width in ns)}``
+    /// :param float normal: The voltage channels rest at before and after the pulse
+    /// :param bool preset_state: Whether the high speed drivers should be preloaded
+    ///                           before the actual pulsing
+    /// :raises ValueError: If channels of the same cluster request different pulse
+    ///                     widths or opposite transitions, a channel is out of range
+    ///                     or a pulse width exceeds 500 ms
+    #[pyo3(signature = (channels, normal=0.0, preset_state=true))]
+    fn fast_pulse<'py>(mut slf: PyRefMut<'py, Self>, channels: HashMap<usize, (f32, u128)>,
+        normal: f32, preset_state: bool) -> PyResult<PyRefMut<'py, Self>> {
+
+        // iterate in channel order so that conflicts are reported consistently
+        let channels: BTreeMap<usize, (f32, u128)> = channels.into_iter().collect();
+
+        let mut cl_nanos: [Option<u128>; 8] = [None; 8];
+        // first channel seen in each cluster along with its transition direction
+        let mut cl_owner: [Option<(usize, f32)>; 8] = [None; 8];
+        let mut chans: Vec<(usize, f32, f32)> = Vec::with_capacity(channels.len());
+
+        for (chan, (voltage, nanos)) in &channels {
+            let (chan, voltage, nanos) = (*chan, *voltage, *nanos);
+
+            if chan >= 64 {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Channel {} is out of range (0-63)", chan)));
+            }
+
+            if nanos == 0 || nanos > FAST_PULSE_MAX_NANOS {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Pulse width of channel {} is {} ns; fast pulses must be \
+                        between 1 ns and 500 ms", chan, nanos)));
+            }
+
+            let cluster = chan / 8;
+            let direction = voltage - normal;
+
+            match (cl_nanos[cluster], cl_owner[cluster]) {
+                (Some(other_nanos), Some((other, _))) if other_nanos != nanos => {
+                    return Err(exceptions::PyValueError::new_err(
+                        format!("Channels {} and {} share cluster {} (channels {}-{}) but \
+                            request different pulse widths ({} ns and {} ns); channels of \
+                            the same cluster must be pulsed with the same width",
+                            other, chan, cluster, cluster*8, cluster*8 + 7,
+                            other_nanos, nanos)));
+                },
+                (_, Some((other, other_dir))) if other_dir * direction < 0.0 => {
+                    return Err(exceptions::PyValueError::new_err(
+                        format!("Channels {} and {} share cluster {} (channels {}-{}) but \
+                            request opposite transitions from {} V; channels of the same \
+                            cluster must all pulse above or all below the normal voltage",
+                            other, chan, cluster, cluster*8, cluster*8 + 7, normal)));
+                },
+                (_, Some((other, other_dir))) => {
+                    // keep track of a channel with an actual transition if possible
+                    if other_dir == 0.0 {
+                        cl_owner[cluster] = Some((chan, direction));
+                    } else {
+                        cl_owner[cluster] = Some((other, other_dir));
+                    }
+                },
+                (_, None) => {
+                    cl_nanos[cluster] = Some(nanos);
+                    cl_owner[cluster] = Some((chan, direction));
+                }
+            }
+
+            chans.push((chan, voltage, normal));
+        }
+
+        let exposure = slf.check_open_pulses(&chans, &cl_nanos)?;
+        slf.queue_ranges(open_pulse_voltages(&chans, &cl_nanos))?;
+
+        match slf._instrument.pulse_slice_fast_open(&chans, &cl_nanos, preset_state) {
+            Ok(_) => {
+                slf.charge(exposure);
+                slf.record_applied(open_pulse_voltages(&chans, &cl_nanos));
+                slf._channels.high_speed(&chans);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
 
+    /// run_table(self, lows, highs, voltages, widths, reads, vread, chunk=None, /)
+    /// --
+    ///
+    /// Run a pulse program described column-wise. Row ``i`` applies a pulse of
+    /// ``voltages[i]`` and ``widths[i]`` nanoseconds between ``lows[i]`` and
+    /// ``highs[i]`` (same semantics as :meth:`~pyarc2.Instrument.pulse_one`)
+    /// optionally followed by a read-out at ``vread`` if ``reads[i]`` is true.
+    /// Rows are queued in Rust and executed in chunks of ``chunk`` rows. Every
+    /// row produces at most one read-out so a chunk may not exceed the number
+    /// of read-outs that fit in the output memory of the instrument, which is
+    /// also the default. The high level
+    /// :meth:`pyarc2.Instrument.run_table` accepts a numpy structured array
+    /// instead of individual columns.
+    ///
+    /// :param lows: A uint64 numpy array with the low voltage channels
+    /// :param highs: A uint64 numpy array with the high voltage channels
+    /// :param voltages: A f32 numpy array with the pulse voltages
+    /// :param widths: A uint64 numpy array with the pulse widths in nanoseconds
+    /// :param reads: A bool numpy array denoting which rows are followed by a read
+    /// :param float vread: The read-out voltage
+    /// :param int chunk: Number of rows per command buffer; defaults to the
+    ///                   largest chunk that fits the output memory
+    /// :return: The currents measured for the rows that requested a read-out
+    ///          in the order of the rows
+    /// :rtype: A numpy f32 array
+    /// :raises ValueError: If the columns are of different length, a channel
+    ///                     is out of range or ``chunk`` is zero or larger
+    ///                     than the output memory allows
+    /// :raises SafetyLimitError: If any row, or the exposure the whole table
+    ///                           adds to a device, violates the safety
+    ///                           limits; the table is checked in full before
+    ///                           any row is queued
+    #[pyo3(signature = (lows, highs, voltages, widths, reads, vread, chunk=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn run_table<'py>(&mut self, py: Python<'py>, lows: PyReadonlyArray1<'py, usize>,
+        highs: PyReadonlyArray1<'py, usize>, voltages: PyReadonlyArray1<'py, f32>,
+        widths: PyReadonlyArray1<'py, u64>, reads: PyReadonlyArray1<'py, bool>,
+        vread: f32, chunk: Option<usize>) -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
+
+        let lows = lows.as_array();
+        let highs = highs.as_array();
+        let voltages = voltages.as_array();
+        let widths = widths.as_array();
+        let reads = reads.as_array();
+
+        let nrows = lows.len();
+        if [highs.len(), voltages.len(), widths.len(), reads.len()].iter().any(|l| *l != nrows) {
+            return Err(exceptions::PyValueError::new_err(
+                "All columns of a pulse table must have the same length"));
+        }
+
+        check_channels(lows.iter().chain(highs.iter()))?;
+
+        let chunk = chunk.unwrap_or(BUFFER_READS);
+        if chunk == 0 || chunk > BUFFER_READS {
+            return Err(exceptions::PyValueError::new_err(
+                format!("chunk must be between 1 and {} rows, got {}", BUFFER_READS, chunk)));
+        }
+
+        // validate the whole table, accumulating the exposure of every
+        // device, before anything is queued
+        let mut total = PendingExposure::default();
+        for i in 0..nrows {
+            self.check_pulses_with(&mut total, &[highs[i]], &[device_key(lows[i], highs[i])],
+                voltages[i], widths[i] as u128, 1)?;
+            if reads[i] {
+                self.check_voltage(lows[i], vread)?;
+            }
+        }
+
+        let mut result: Vec<f32> = Vec::with_capacity(nrows);
+        let mut row = 0usize;
+
+        while row < nrows {
+            let end = (row + chunk).min(nrows);
+            let mut read_chans: Vec<usize> = Vec::with_capacity(end - row);
+
+            for i in row..end {
+                self.queue_ranges([(highs[i], voltages[i])])?;
+                self._instrument.pulse_one(lows[i], highs[i], voltages[i], widths[i] as u128)
+                    .map_err(ArC2Error::new_exception)?;
+                let mut exposure = PendingExposure::default();
+                exposure.add(device_key(lows[i], highs[i]), 1,
+                    pulse_dose(voltages[i], widths[i] as u128, 1));
+                self.charge(exposure);
+                self.record_applied([(highs[i], voltages[i])]);
+                self._channels.soft_ground(&[lows[i], highs[i]]);
+                if reads[i] {
+                    self.queue_read_one(lows[i], highs[i], vread)?;
+                    read_chans.push(highs[i]);
+                }
+            }
+
+            self._instrument.execute().map_err(ArC2Error::new_exception)?;
+            self._instrument.wait();
+
+            for chan in read_chans {
+                let data = self.collect_reads(1, &[chan])?;
+                result.push(data[0][0]);
+            }
+
+            row = end;
+        }
+
+        Ok(result.into_pyarray_bound(py))
+    }
+
     /// pulse_all(self, voltage, nanos, order, /)
     /// --
     ///
@@ -1223,8 +4701,17 @@ impl PyInstrument {
     fn pulse_all<'py>(mut slf: PyRefMut<'py, Self>, voltage: f32, nanos: u128, order: PyBiasOrder)
         -> PyResult<PyRefMut<'py, Self>> {
 
+        let chans: Vec<usize> = (0..64).collect();
+        let exposure = slf.check_pulses(&chans, &all_devices(), voltage, nanos, 1)?;
+        slf.queue_ranges(chans.iter().map(|c| (*c, voltage)))?;
+
         match slf._instrument.pulse_all(voltage, nanos, order.into()) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf.charge(exposure);
+                slf.record_applied(chans.iter().map(|c| (*c, voltage)));
+                slf._channels.soft_ground(&chans);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
@@ -1233,7 +4720,7 @@ impl PyInstrument {
     /// --
     ///
     /// Pulse and then read a crosspoint. Same semantics as ``pulse_one`` and
-    /// ``read_one`` apply.
+    /// ``read_one`` apply, including selector gating in 1T1R mode.
     ///
     /// :param int low: The low voltage channel
     /// :param int high: The high voltage channel
@@ -1243,8 +4730,23 @@ impl PyInstrument {
     /// :return: The current between the specified crosspoints at ``vread`` after
     ///          a ``vpulse`` pulse of ``nanos`` duration has been applied
     /// :rtype: float
-    fn pulseread_one(&mut self, low: usize, high: usize, vpulse: f32, nanos: u128, vread: f32) -> f32 {
-        self._instrument.pulseread_one(low, high, vpulse, nanos, vread).unwrap()
+    fn pulseread_one(&mut self, low: usize, high: usize, vpulse: f32, nanos: u128, vread: f32)
+        -> PyResult<f32> {
+        self.check_voltage(low, vread)?;
+        let exposure = self.check_pulses(&[high], &[device_key(low, high)], vpulse, nanos, 1)?;
+        let gated = self.gate_select(low, high)?;
+        self.queue_ranges([(low, vread), (high, vpulse)])?;
+        let mut res = self._instrument.pulseread_one(low, high, vpulse, nanos, vread)
+            .map_err(ArC2Error::new_exception)?;
+        self.charge(exposure);
+        self.record_applied([(high, vpulse), (low, -vread)]);
+        self.correct(&[high], std::slice::from_mut(&mut res));
+        self._channels.soft_ground(&[low, high]);
+        if gated.is_some() {
+            self.gate_restore(gated)?;
+            self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        }
+        Ok(res)
     }
 
     /// pulseread_slice(self, chan, vpulse, nanos, vread, /)
@@ -1263,10 +4765,22 @@ impl PyInstrument {
     ///          applied
     /// :rtype: A numpy f32 array
     fn pulseread_slice<'py>(&mut self, py: Python<'py>, chan: usize, vpulse: f32,
-        nanos: u128, vread: f32) -> Bound<'py, PyArray<f32, Ix1>> {
-
-        let data = self._instrument.pulseread_slice(chan, vpulse, nanos, vread).unwrap();
-        data.into_pyarray_bound(py)
+        nanos: u128, vread: f32) -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
+
+        let highs = crossing_channels(chan);
+        let devices: Vec<(usize, usize)> = highs.iter().map(|h| device_key(chan, *h)).collect();
+        self.check_voltage(chan, vread)?;
+        let exposure = self.check_pulses(&highs, &devices, vpulse, nanos, 1)?;
+        self.queue_ranges(highs.iter().map(|h| (*h, vpulse)).chain([(chan, vread)]))?;
+
+        let mut data = self._instrument.pulseread_slice(chan, vpulse, nanos, vread)
+            .map_err(ArC2Error::new_exception)?;
+        self.charge(exposure);
+        self.record_applied(highs.iter().map(|h| (*h, vpulse)).chain([(chan, -vread)]));
+        self.correct(&highs, &mut data);
+        self._channels.soft_ground(&highs);
+        self._channels.soft_ground(&[chan]);
+        Ok(data.into_pyarray_bound(py))
     }
 
     /// pulseread_slice_masked(self, chan, mask, vpulse, nanos, vread, /)
@@ -1287,12 +4801,22 @@ impl PyInstrument {
     /// :rtype: A numpy f32 array
     fn pulseread_slice_masked<'py>(&mut self, py: Python<'py>, chan: usize,
         mask: PyReadonlyArray1<'py, usize>, vpulse: f32, nanos: u128,
-        vread: f32) -> Bound<'py, PyArray<f32, Ix1>> {
+        vread: f32) -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
 
         let slice = mask.as_slice().unwrap();
-        let data = self._instrument.pulseread_slice_masked(chan, slice, vpulse, nanos, vread)
-            .unwrap();
-        data.into_pyarray_bound(py)
+        let devices: Vec<(usize, usize)> = slice.iter().map(|h| device_key(chan, *h)).collect();
+        self.check_voltage(chan, vread)?;
+        let exposure = self.check_pulses(slice, &devices, vpulse, nanos, 1)?;
+        self.queue_ranges(slice.iter().map(|h| (*h, vpulse)).chain([(chan, vread)]))?;
+
+        let mut data = self._instrument.pulseread_slice_masked(chan, slice, vpulse, nanos, vread)
+            .map_err(ArC2Error::new_exception)?;
+        self.charge(exposure);
+        self.record_applied(slice.iter().map(|h| (*h, vpulse)).chain([(chan, -vread)]));
+        self.correct(&crossing_channels(chan), &mut data);
+        self._channels.soft_ground(slice);
+        self._channels.soft_ground(&[chan]);
+        Ok(data.into_pyarray_bound(py))
     }
 
     /// pulseread_all(self, vpulse, nanos, vread, order, /)
@@ -1311,13 +4835,308 @@ impl PyInstrument {
     ///          cronsspoint
     /// :rtype: A numpy (2, 2) f32 ndarray
     fn pulseread_all<'py>(&mut self, py: Python<'py>, vpulse: f32, nanos: u128,
-        vread: f32, order: PyBiasOrder) -> Bound<'py, PyArray<f32, Ix2>> {
+        vread: f32, order: PyBiasOrder) -> PyResult<Bound<'py, PyArray<f32, Ix2>>> {
 
-        let data = self._instrument.pulseread_all(vpulse, nanos, vread, order.into())
-            .unwrap();
+        let chans: Vec<usize> = (0..64).collect();
+        for chan in &chans {
+            self.check_voltage(*chan, vread)?;
+        }
+        let exposure = self.check_pulses(&chans, &all_devices(), vpulse, nanos, 1)?;
+        self.queue_ranges(chans.iter().flat_map(|c| [(*c, vread), (*c, vpulse)]))?;
+
+        let rows_biased = matches!(order._inner, BiasOrder::Rows);
+        let mut data = self._instrument.pulseread_all(vpulse, nanos, vread, order.into())
+            .map_err(ArC2Error::new_exception)?;
+        self.charge(exposure);
+        let biased = crossing_channels(if rows_biased { 16 } else { 0 });
+        self.record_applied(chans.iter().map(|c| (*c, vpulse))
+            .chain(biased.iter().map(|line| (*line, -vread))));
+        self.correct_grid(&mut data, rows_biased);
+        self._channels.ground_all();
         let array = data.into_pyarray_bound(py);
-        array.borrow().reshape((32, 32)).unwrap()
+        Ok(array.borrow().reshape((32, 32)).unwrap())
+
+    }
+
+    /// configure_current_source(self, cref, resistor, /)
+    /// --
+    ///
+    /// Set up the current source used by :meth:`~pyarc2.Instrument.force_current`
+    /// and :meth:`~pyarc2.Instrument.current_ramp`. The output current is
+    /// ``(CSET - CREF) / resistor``, so ``resistor`` must be the value of the
+    /// current set resistor fitted on the board and ``cref`` the reference
+    /// voltage the current is sourced from or sunk to. As specified for
+    /// :class:`~pyarc2.AuxDACFn` ``CSET`` must stay within 1.5 V of ``CREF``,
+    /// which limits the available current to ±1.5 V / ``resistor``, and
+    /// ``CREF`` should leave at least 3 V of headroom to the expected
+    /// operating point of the device. Nothing is sent to the instrument until
+    /// current is forced.
+    ///
+    /// :param float cref: The current source reference voltage
+    /// :param float resistor: The current set resistor in Ω
+    /// :raises ValueError: If the resistor is not a positive number
+    fn configure_current_source(&mut self, cref: f32, resistor: f32) -> PyResult<()> {
+        if resistor.is_nan() || resistor <= 0.0 {
+            return Err(exceptions::PyValueError::new_err(
+                "Current set resistor must be a positive number"));
+        }
+        self._csrc = Some((cref, resistor));
+        Ok(())
+    }
+
+    /// force_current(self, chan, amps, averaging=True, /)
+    /// --
+    ///
+    /// Force a current on ``chan`` using the current source and return the
+    /// resulting voltage on the channel. This programs ``CREF`` and ``CSET``,
+    /// switches the channel to the current source and flushes the command
+    /// buffer. Positive currents are sourced into the channel. The channel
+    /// stays connected to the current source until it is reconfigured, for
+    /// instance with :meth:`~pyarc2.Instrument.ground_all`.
+    ///
+    /// >>> arc.configure_current_source(5.0, 10e3)
+    /// >>> voltage = arc.force_current(20, 10e-6)
+    ///
+    /// :param int chan: The channel to force current on
+    /// :param float amps: The current in A
+    /// :param bool averaging: Whether to average the voltage read-out
+    /// :return: The voltage of the channel
+    /// :rtype: float
+    /// :raises ValueError: If the current source is not configured or the
+    ///                     current is beyond its range
+    #[pyo3(signature = (chan, amps, averaging=true))]
+    fn force_current(&mut self, chan: usize, amps: f32, averaging: bool) -> PyResult<f32> {
+        let cset = self.cset_for(amps)?;
+        let (cref, _) = self._csrc.unwrap();
+
+        // the channel can swing up to the reference voltage
+        self.check_voltage(chan, cref)?;
+        self.queue_ranges([(chan, cref)])?;
+
+        let config = [(AuxDACFn::CREF, cref), (AuxDACFn::CSET, cset)];
+        self._instrument.config_aux_channels(&config).map_err(ArC2Error::new_exception)?;
+        for (dac, voltage) in &config {
+            self._periph.aux[aux_index(*dac)] = Some(*voltage);
+        }
+        self._instrument.connect_to_current_source(&[chan]).map_err(ArC2Error::new_exception)?;
+        self._channels.current_source(&[chan]);
+        self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        self._instrument.wait();
+
+        let voltages = self._instrument.vread_channels(&[chan], averaging)
+            .map_err(ArC2Error::new_exception)?;
+
+        Ok(voltages[0])
+    }
+
+    /// current_ramp(self, chan, istart, istep, istop, inter_nanos=0, averaging=True, /)
+    /// --
+    ///
+    /// Step the current forced on ``chan`` from ``istart`` to ``istop`` and
+    /// read the voltage of the channel at every step. All steps run in a
+    /// single command buffer; ``inter_nanos`` of settling time are added
+    /// before each voltage read. Afterwards the current is set to zero and
+    /// all channels are grounded.
+    ///
+    /// >>> # I-V curve of the device on channel 20 from 0 to 100 μA
+    /// >>> voltages = arc.current_ramp(20, 0.0, 1e-6, 100e-6, 1000)
+    ///
+    /// :param int chan: The channel to force current on
+    /// :param float istart: The first current in A
+    /// :param float istep: The current step in A
+    /// :param float istop: The last current in A
+    /// :param int inter_nanos: Settling time before each read in nanoseconds
+    /// :param bool averaging: Whether to average the voltage read-outs
+    /// :return: The voltage of the channel at every current step
+    /// :rtype: A numpy f32 array
+    /// :raises ValueError: If the current source is not configured or any
+    ///                     current is beyond its range
+    #[pyo3(signature = (chan, istart, istep, istop, inter_nanos=0, averaging=true))]
+    #[allow(clippy::too_many_arguments)]
+    fn current_ramp<'py>(&mut self, py: Python<'py>, chan: usize, istart: f32, istep: f32,
+        istop: f32, inter_nanos: u128, averaging: bool) -> PyResult<Bound<'py, PyArray<f32, Ix1>>> {
+
+        if istep == 0.0 || istep.is_nan() || (istop - istart) * istep < 0.0 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Cannot reach {} A from {} A in steps of {} A", istop, istart, istep)));
+        }
+
+        let nsteps = ramp_steps(istart, istep, istop);
+        let csets: Vec<f32> = (0..nsteps)
+            .map(|i| self.cset_for(ramp_value(istart, istep, i)))
+            .collect::<PyResult<_>>()?;
+        let (cref, _) = self._csrc.unwrap();
+
+        self.check_voltage(chan, cref)?;
+        self.queue_ranges([(chan, cref)])?;
+
+        self._instrument.config_aux_channels(&[(AuxDACFn::CREF, cref), (AuxDACFn::CSET, csets[0])])
+            .map_err(ArC2Error::new_exception)?;
+        self._instrument.connect_to_current_source(&[chan]).map_err(ArC2Error::new_exception)?;
+
+        for cset in &csets {
+            self._instrument.config_aux_channels(&[(AuxDACFn::CSET, *cset)])
+                .map_err(ArC2Error::new_exception)?;
+            if inter_nanos > 0 {
+                self._instrument.add_delay(inter_nanos).map_err(ArC2Error::new_exception)?;
+            }
+            self._instrument.vread_channels_deferred(&[chan], averaging)
+                .map_err(ArC2Error::new_exception)?;
+        }
+
+        self._instrument.config_aux_channels(&[(AuxDACFn::CSET, cref)])
+            .map_err(ArC2Error::new_exception)?;
+        self._periph.aux[aux_index(AuxDACFn::CREF)] = Some(cref);
+        self._periph.aux[aux_index(AuxDACFn::CSET)] = Some(cref);
+        self._instrument.ground_all().map_err(ArC2Error::new_exception)?;
+        self._channels.ground_all();
+        self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        self._instrument.wait();
+
+        let voltages: Vec<f32> = self.collect_voltages(nsteps, &[chan])?
+            .into_iter().map(|r| r[0]).collect();
+
+        Ok(voltages.into_pyarray_bound(py))
+    }
+
+    /// four_wire(self, force_high, force_low, sense_high, sense_low, voltage=None, current=None, nreads=1, averaging=True, /)
+    /// --
+    ///
+    /// Four-wire resistance measurement. Either a voltage or a current is
+    /// forced between ``force_high`` and ``force_low`` and the voltage is
+    /// sensed between ``sense_high`` and ``sense_low``, eliminating the
+    /// contribution of lead and contact resistance. When forcing a voltage,
+    /// ``force_low`` is biased at ``-voltage`` and the current is read from
+    /// ``force_high``, same as :meth:`~pyarc2.Instrument.read_one`. When
+    /// forcing a current the current source, configured with
+    /// :meth:`~pyarc2.Instrument.configure_current_source`, drives
+    /// ``force_high`` and ``force_low`` is held at 0.0 V. In both modes the
+    /// sense channels are left open (high impedance) so that no current
+    /// flows through them and only the forcing channels are biased. The
+    /// measurement is repeated ``nreads`` times in a single command buffer
+    /// and the results are averaged. All channels are grounded afterwards.
+    /// As every repetition must fit in the output memory of the instrument
+    /// ``nreads`` can be at most 128 when forcing a voltage (a voltage and a
+    /// current read-out per repetition) and 256 when forcing a current.
+    ///
+    /// >>> # force 10 μA between 16 and 17 and sense between 18 and 19
+    /// >>> (res, v, i) = arc.four_wire(16, 17, 18, 19, current=10e-6, nreads=8)
+    ///
+    /// :param int force_high: The high forcing channel
+    /// :param int force_low: The low forcing channel
+    /// :param int sense_high: The high sensing channel
+    /// :param int sense_low: The low sensing channel
+    /// :param float voltage: The voltage to force
+    /// :param float current: The current to force in A
+    /// :param int nreads: Number of measurements to average; at most 128
+    ///                    when forcing a voltage and 256 when forcing a
+    ///                    current
+    /// :param bool averaging: Whether to average each voltage read-out
+    /// :return: A tuple with the resistance, the sensed voltage and the
+    ///          forced or measured current
+    /// :rtype: (float, float, float)
+    /// :raises ValueError: If neither or both of ``voltage`` and ``current``
+    ///                     are provided, ``nreads`` is zero or above its
+    ///                     limit or the four channels are not distinct
+    #[pyo3(signature = (force_high, force_low, sense_high, sense_low, voltage=None,
+        current=None, nreads=1, averaging=true))]
+    #[allow(clippy::too_many_arguments)]
+    fn four_wire(&mut self, force_high: usize, force_low: usize, sense_high: usize,
+        sense_low: usize, voltage: Option<f32>, current: Option<f32>, nreads: usize,
+        averaging: bool) -> PyResult<(f32, f32, f32)> {
+
+        // forcing a voltage stores a voltage and a current read-out per read
+        let max_reads = if voltage.is_some() { BUFFER_READS / 2 } else { BUFFER_READS };
+        if nreads == 0 || nreads > max_reads {
+            return Err(exceptions::PyValueError::new_err(
+                format!("nreads must be between 1 and {} when forcing a {}, got {}", max_reads,
+                    if voltage.is_some() { "voltage" } else { "current" }, nreads)));
+        }
+
+        let chans = [force_high, force_low, sense_high, sense_low];
+        check_channels(&chans)?;
+        if (1..chans.len()).any(|i| chans[i..].contains(&chans[i - 1])) {
+            return Err(exceptions::PyValueError::new_err(
+                "Forcing and sensing channels must all be different"));
+        }
+
+        let sense = [sense_high, sense_low];
+        let mut vsense: Vec<f32> = Vec::with_capacity(nreads);
+        let mut isense: Vec<f32> = Vec::with_capacity(nreads);
+
+        match (voltage, current) {
+            (Some(v), None) => {
+                self.check_voltage(force_low, v)?;
+                self.queue_ranges([(force_low, v)])?;
+
+                self._instrument.open_channels(&sense).map_err(ArC2Error::new_exception)?;
+                self._instrument.config_channels(&[(force_low as u16, -v), (force_high as u16, 0.0)],
+                    None).map_err(ArC2Error::new_exception)?;
+                self.record_applied([(force_low, -v), (force_high, 0.0)]);
+
+                for _ in 0..nreads {
+                    self._instrument.vread_channels_deferred(&sense, averaging)
+                        .map_err(ArC2Error::new_exception)?;
+                    self._instrument.read_slice_open_deferred(&[force_high], false)
+                        .map_err(ArC2Error::new_exception)?;
+                }
+
+                self._instrument.ground_all().map_err(ArC2Error::new_exception)?;
+                self._channels.ground_all();
+                self._instrument.execute().map_err(ArC2Error::new_exception)?;
+                self._instrument.wait();
+
+                // voltage and current records are interleaved in the output
+                // buffer so they are picked one at a time
+                for _ in 0..nreads {
+                    let vs = self.collect_voltages(1, &sense)?.remove(0);
+                    vsense.push(vs[0] - vs[1]);
+                    isense.push(self.collect_reads(1, &[force_high])?[0][0]);
+                }
+            },
+            (None, Some(amps)) => {
+                let cset = self.cset_for(amps)?;
+                let (cref, _) = self._csrc.unwrap();
+                self.check_voltage(force_high, cref)?;
+                self.queue_ranges([(force_high, cref)])?;
+
+                self._instrument.open_channels(&sense).map_err(ArC2Error::new_exception)?;
+                self._instrument.config_channels(&[(force_low as u16, 0.0)], None)
+                    .map_err(ArC2Error::new_exception)?;
+                self._instrument.config_aux_channels(&[(AuxDACFn::CREF, cref), (AuxDACFn::CSET, cset)])
+                    .map_err(ArC2Error::new_exception)?;
+                self._instrument.connect_to_current_source(&[force_high])
+                    .map_err(ArC2Error::new_exception)?;
+
+                for _ in 0..nreads {
+                    self._instrument.vread_channels_deferred(&sense, averaging)
+                        .map_err(ArC2Error::new_exception)?;
+                }
+
+                self._instrument.config_aux_channels(&[(AuxDACFn::CSET, cref)])
+                    .map_err(ArC2Error::new_exception)?;
+                self._periph.aux[aux_index(AuxDACFn::CREF)] = Some(cref);
+                self._periph.aux[aux_index(AuxDACFn::CSET)] = Some(cref);
+                self._instrument.ground_all().map_err(ArC2Error::new_exception)?;
+                self._channels.ground_all();
+                self._instrument.execute().map_err(ArC2Error::new_exception)?;
+                self._instrument.wait();
+
+                for vs in self.collect_voltages(nreads, &sense)? {
+                    vsense.push(vs[0] - vs[1]);
+                    isense.push(amps);
+                }
+            },
+            _ => {
+                return Err(exceptions::PyValueError::new_err(
+                    "Exactly one of voltage or current must be provided"));
+            }
+        }
 
+        let vmean = vsense.iter().sum::<f32>() / (nreads as f32);
+        let imean = isense.iter().sum::<f32>() / (nreads as f32);
+
+        Ok((vmean / imean, vmean, imean))
     }
 
     /// vread_channels(self, chans, averaging, /)
@@ -1394,55 +5213,436 @@ impl PyInstrument {
     ///
     /// :param mode: A variant of :class:`pyarc2.ControlMode`
     fn set_control_mode<'py>(mut slf: PyRefMut<'py, Self>, mode: PyControlMode) -> PyResult<PyRefMut<'py, Self>> {
-        match slf._instrument.set_control_mode(mode.into()) {
-            Ok(_) => Ok(slf),
+        let mode: ControlMode = mode.into();
+        match slf._instrument.set_control_mode(mode.clone()) {
+            Ok(_) => {
+                slf._periph.control = Some(mode);
+                Ok(slf)
+            },
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// set_logic(self, channel_mask, enable, /)
+    /// --
+    ///
+    /// Set the digital I/Os specified by ``mask`` to either high (when ``enable`` is ``True``)
+    /// or low (when ``enable`` is ``False``). An :meth:`~pyarc2.Instrument.execute` is
+    /// required to actually load the configuration.
+    ///
+    /// :param int mask: A ``u32`` bitmask of the channels this function will be applied to
+    /// :param cl0: Direction of GPIO cluster 0 (channels 0–7). Defaults to output.
+    /// :param cl1: Direction of GPIO cluster 1 (channels 8–15). Defaults to output.
+    /// :param cl2: Direction of GPIO cluster 2 (channels 16–23). Defaults to output.
+    /// :param cl3: Direction of GPIO cluster 3 (channels 24–32). Defaults to output.
+    /// :raises ValueError: If a cluster direction conflicts with a named pin
+    ///                     (see :meth:`~pyarc2.Instrument.define_pin`) or the
+    ///                     current logic level does not match the declared
+    ///                     peripheral level
+    #[pyo3(signature = (mask, cl0=None, cl1=None, cl2=None, cl3=None))]
+    fn set_logic<'py>(mut slf: PyRefMut<'py, Self>, mask: u32,
+        cl0: Option<PyIODir>, cl1: Option<PyIODir>, cl2: Option<PyIODir>, cl3: Option<PyIODir>)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        let rawmask = mask;
+        let mask = IOMask::from_vals(&[mask]);
+
+        let actual_cl0 = match cl0 {
+            Some(x) => x._inner,
+            None => IODir::OUT
+        };
+
+        let actual_cl1 = match cl1 {
+            Some(x) => x._inner,
+            None => IODir::OUT
+        };
+
+        let actual_cl2 = match cl2 {
+            Some(x) => x._inner,
+            None => IODir::OUT
+        };
+
+        let actual_cl3 = match cl3 {
+            Some(x) => x._inner,
+            None => IODir::OUT
+        };
+
+        slf._gpio.check_dirs(&[actual_cl0, actual_cl1, actual_cl2, actual_cl3])?;
+        if let Some(level) = &slf._periph.logic_level {
+            slf.check_peripheral_level(level)?;
+        }
+
+        match slf._instrument.set_logic(actual_cl0, actual_cl1, actual_cl2, actual_cl3, &mask) {
+            Ok(_) => {
+                slf._periph.logic = Some((rawmask, [actual_cl0, actual_cl1, actual_cl2, actual_cl3]));
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
 
-    /// set_logic(self, channel_mask, enable, /)
+    /// define_pin(self, name, pin, direction, /)
+    /// --
+    ///
+    /// Assign a name to a GPIO pin. As the direction of the GPIOs is set per
+    /// cluster of 8 pins (see :class:`~pyarc2.IODir`) all named pins of a
+    /// cluster must share the same direction; this is also checked against
+    /// the cluster directions last configured with
+    /// :meth:`~pyarc2.Instrument.set_logic`. Redefining an existing name
+    /// replaces it.
+    ///
+    /// >>> arc.define_pin('trig', 0, IODir.OUT)
+    /// >>> arc.define_pin('ready', 8, IODir.IN)
+    ///
+    /// :param str name: The name of the pin
+    /// :param int pin: The GPIO number (0–31)
+    /// :param direction: A variant of :class:`~pyarc2.IODir`
+    /// :raises ValueError: If the pin is out of range, already named or its
+    ///                     direction conflicts with its cluster
+    fn define_pin(&mut self, name: String, pin: usize, direction: PyIODir) -> PyResult<()> {
+
+        let direction: IODir = direction.into();
+
+        if pin >= 32 {
+            return Err(exceptions::PyValueError::new_err(
+                format!("GPIO {} is out of range (0-31)", pin)));
+        }
+
+        let cluster = pin / 8;
+        let dirname = |d: IODir| if d == IODir::OUT { "output" } else { "input" };
+
+        for (other, (opin, odir)) in &self._gpio.pins {
+            if *other == name {
+                continue;
+            }
+            if *opin == pin {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("GPIO {} is already named \"{}\"", pin, other)));
+            }
+            if *opin / 8 == cluster && *odir != direction {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("GPIO {} cannot be an {} as pin \"{}\" ({}) in the same cluster \
+                        (GPIO {}-{}) is an {}", pin, dirname(direction), other, opin,
+                        cluster*8, cluster*8 + 7, dirname(*odir))));
+            }
+        }
+
+        if let Some((_, dirs)) = &self._periph.logic {
+            let has_pins = self._gpio.pins.iter()
+                .any(|(other, (opin, _))| *other != name && *opin / 8 == cluster);
+            if !has_pins && dirs[cluster] != direction {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("GPIO {} cannot be an {} as cluster {} (GPIO {}-{}) is configured \
+                        as {}", pin, dirname(direction), cluster, cluster*8, cluster*8 + 7,
+                        dirname(dirs[cluster]))));
+            }
+        }
+
+        self._gpio.pins.insert(name, (pin, direction));
+        Ok(())
+    }
+
+    /// pins(self, /)
+    /// --
+    ///
+    /// Get all named GPIO pins.
+    ///
+    /// :return: A dict of ``{name: (pin, direction)}``
+    fn pins(&self) -> BTreeMap<String, (usize, PyIODir)> {
+        self._gpio.pins.iter()
+            .map(|(name, (pin, dir))| (name.clone(), (*pin, (*dir).into())))
+            .collect()
+    }
+
+    /// set_pins(self, states, /)
+    /// --
+    ///
+    /// Set named output pins high (``True``) or low (``False``). Outputs not
+    /// included in ``states`` retain their previous state and cluster
+    /// directions are derived from the named pins; clusters without named
+    /// pins keep the direction last set with
+    /// :meth:`~pyarc2.Instrument.set_logic`. An
+    /// :meth:`~pyarc2.Instrument.execute` is required to actually load the
+    /// configuration.
+    ///
+    /// >>> arc.set_pins({'trig': True, 'enable': False}).execute()
+    ///
+    /// :param dict states: A dict of ``{name: state}``
+    /// :raises KeyError: If a pin has not been named
+    /// :raises ValueError: If a pin is an input, the direction of a cluster is
+    ///                     not known or the current logic level does not
+    ///                     match the declared peripheral level
+    fn set_pins<'py>(mut slf: PyRefMut<'py, Self>, states: BTreeMap<String, bool>)
+        -> PyResult<PyRefMut<'py, Self>> {
+        let states: Vec<(String, bool)> = states.into_iter().collect();
+        let pins = slf.output_pins(&states)?;
+        slf.queue_pins(&pins)?;
+        Ok(slf)
+    }
+
+    /// set_gpio(self, pins, state, /)
+    /// --
+    ///
+    /// Set GPIOs by number high (``True``) or low (``False``) retaining the
+    /// state of all other outputs. Unlike :meth:`~pyarc2.Instrument.set_logic`
+    /// this only affects the specified GPIOs and can be freely interleaved with
+    /// other operations in the command buffer.
+    ///
+    /// :param pins: A list of GPIO numbers (0–31)
+    /// :param bool state: The state to set the GPIOs to
+    /// :raises ValueError: If a GPIO is out of range, belongs to an input cluster
+    ///                     or the direction of a cluster is not known
+    fn set_gpio<'py>(mut slf: PyRefMut<'py, Self>, pins: Vec<usize>, state: bool)
+        -> PyResult<PyRefMut<'py, Self>> {
+        let pins: Vec<(usize, bool)> = pins.into_iter().map(|p| (p, state)).collect();
+        slf.queue_pins(&pins)?;
+        Ok(slf)
+    }
+
+    /// pulse_pin(self, name, nanos, active_high=True, /)
+    /// --
+    ///
+    /// Queue a digital pulse of ``nanos`` nanoseconds on a named output pin.
+    /// The pin is driven to its active state, held for ``nanos`` and returned
+    /// to its inactive state. As with every GPIO operation this is part of the
+    /// command buffer and is executed in sequence with the operations queued
+    /// before and after it.
+    ///
+    /// >>> # raise 'gate', pulse the device, lower 'gate' and read
+    /// >>> arc.set_pins({'gate': True}) \
+    /// >>>    .pulse_one(3, 20, 2.0, 1000) \
+    /// >>>    .set_pins({'gate': False}) \
+    /// >>>    .read_one_deferred(3, 20, 0.2) \
+    /// >>>    .pulse_pin('done', 500) \
+    /// >>>    .execute()
+    /// >>> current = arc.pick_one(DataMode.All, ReadType.Current)[20]
+    ///
+    /// :param str name: The name of the pin
+    /// :param int nanos: Duration of the active state in nanoseconds
+    /// :param bool active_high: Whether the active state is high
+    /// :raises KeyError: If the pin has not been named
+    /// :raises ValueError: If the pin is an input
+    #[pyo3(signature = (name, nanos, active_high=true))]
+    fn pulse_pin<'py>(mut slf: PyRefMut<'py, Self>, name: String, nanos: u128, active_high: bool)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        let active = slf.output_pins(&[(name.clone(), active_high)])?;
+        let inactive = slf.output_pins(&[(name, !active_high)])?;
+
+        slf.queue_pins(&active)?;
+        slf._instrument.add_delay(nanos).map_err(ArC2Error::new_exception)?;
+        slf.queue_pins(&inactive)?;
+
+        Ok(slf)
+    }
+
+    /// get_pins(self, names=None, /)
+    /// --
+    ///
+    /// Read the state of named input pins from ArC TWO. This flushes the
+    /// command buffer.
+    ///
+    /// :param names: The names of the pins to read or ``None`` to read all
+    ///               named inputs
+    /// :return: A dict of ``{name: state}``
+    /// :raises KeyError: If a pin has not been named
+    /// :raises ValueError: If a pin is not an input
+    #[pyo3(signature = (names=None))]
+    fn get_pins(&mut self, names: Option<Vec<String>>) -> PyResult<BTreeMap<String, bool>> {
+
+        let names: Vec<String> = match names {
+            Some(n) => n,
+            None => self._gpio.pins.iter()
+                .filter(|(_, (_, dir))| *dir == IODir::IN)
+                .map(|(name, _)| name.clone()).collect()
+        };
+
+        let mut pins: Vec<(String, usize)> = Vec::with_capacity(names.len());
+        for name in names {
+            let (pin, dir) = self._gpio.pin(&name)?;
+            if dir != IODir::IN {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("GPIO pin \"{}\" ({}) is not an input", name, pin)));
+            }
+            pins.push((name, pin));
+        }
+
+        let levels = self._instrument.read_logic().map_err(ArC2Error::new_exception)?;
+
+        Ok(pins.into_iter().map(|(name, pin)| (name, (levels >> pin) & 1 == 1)).collect())
+    }
+
+    /// trigger_out(self, pin, edge=TriggerEdge.Rising, /)
+    /// --
+    ///
+    /// Queue the leading edge of a trigger on GPIO ``pin``. The GPIO is
+    /// driven to its active level right before the operation queued next,
+    /// which starts immediately afterwards, and stays there until
+    /// :meth:`~pyarc2.Instrument.trigger_end` is queued. Bracketing an
+    /// operation between the two synchronises external equipment, such as
+    /// an oscilloscope, with it and the trigger spans the whole operation.
+    /// ``edge`` denotes the leading edge of the trigger; the GPIO idles at
+    /// the opposite level.
+    ///
+    /// >>> # trigger the scope on GPIO 3 for the duration of the pulse
+    /// >>> arc.trigger_out(3) \
+    /// >>>    .pulse_one(3, 20, 2.0, 1000) \
+    /// >>>    .trigger_end(3) \
+    /// >>>    .execute()
+    ///
+    /// :param int pin: The GPIO number (0–31)
+    /// :param edge: A variant of :class:`~pyarc2.TriggerEdge`
+    /// :raises ValueError: If the GPIO is out of range or belongs to an input
+    ///                     cluster
+    #[pyo3(signature = (pin, edge=None))]
+    fn trigger_out<'py>(mut slf: PyRefMut<'py, Self>, pin: usize, edge: Option<PyTriggerEdge>)
+        -> PyResult<PyRefMut<'py, Self>> {
+
+        let rising = match edge {
+            Some(e) => e._inner == TriggerEdge::Rising,
+            None => true
+        };
+        slf.queue_pins(&[(pin, rising)])?;
+
+        Ok(slf)
+    }
+
+    /// trigger_end(self, pin, edge=TriggerEdge.Rising, /)
     /// --
     ///
-    /// Set the digital I/Os specified by ``mask`` to either high (when ``enable`` is ``True``)
-    /// or low (when ``enable`` is ``False``). An :meth:`~pyarc2.Instrument.execute` is
-    /// required to actually load the configuration.
+    /// Queue the trailing edge of a trigger started with
+    /// :meth:`~pyarc2.Instrument.trigger_out`, returning GPIO ``pin`` to its
+    /// idle level once the operations queued in between have completed.
+    /// ``edge`` must be the same as the one used to start the trigger.
     ///
-    /// :param int mask: A ``u32`` bitmask of the channels this function will be applied to
-    /// :param cl0: Direction of GPIO cluster 0 (channels 0–7). Defaults to output.
-    /// :param cl1: Direction of GPIO cluster 1 (channels 8–15). Defaults to output.
-    /// :param cl2: Direction of GPIO cluster 2 (channels 16–23). Defaults to output.
-    /// :param cl3: Direction of GPIO cluster 3 (channels 24–32). Defaults to output.
-    #[pyo3(signature = (mask, cl0=None, cl1=None, cl2=None, cl3=None))]
-    fn set_logic<'py>(mut slf: PyRefMut<'py, Self>, mask: u32,
-        cl0: Option<PyIODir>, cl1: Option<PyIODir>, cl2: Option<PyIODir>, cl3: Option<PyIODir>)
+    /// :param int pin: The GPIO number (0–31)
+    /// :param edge: A variant of :class:`~pyarc2.TriggerEdge`
+    /// :raises ValueError: If the GPIO is out of range or belongs to an input
+    ///                     cluster
+    #[pyo3(signature = (pin, edge=None))]
+    fn trigger_end<'py>(mut slf: PyRefMut<'py, Self>, pin: usize, edge: Option<PyTriggerEdge>)
         -> PyResult<PyRefMut<'py, Self>> {
 
-        let mask = IOMask::from_vals(&[mask]);
-
-        let actual_cl0 = match cl0 {
-            Some(x) => x._inner,
-            None => IODir::OUT
+        let rising = match edge {
+            Some(e) => e._inner == TriggerEdge::Rising,
+            None => true
         };
+        slf.queue_pins(&[(pin, !rising)])?;
 
-        let actual_cl1 = match cl1 {
-            Some(x) => x._inner,
-            None => IODir::OUT
-        };
+        Ok(slf)
+    }
 
-        let actual_cl2 = match cl2 {
-            Some(x) => x._inner,
-            None => IODir::OUT
-        };
+    /// wait_for(self, condition, timeout=None, /)
+    /// --
+    ///
+    /// Pause the sequence of operations until ``condition`` is met. Time
+    /// conditions (:meth:`~pyarc2.WaitFor.Nanos`, :meth:`~pyarc2.WaitFor.Millis`)
+    /// are queued as a delay in the command buffer. For
+    /// :meth:`~pyarc2.WaitFor.Trigger` conditions the command buffer is flushed,
+    /// so that everything queued so far is executed, and the call then blocks
+    /// until the requested edge is seen on the GPIO. Operations queued
+    /// afterwards will only run after the edge has arrived.
+    ///
+    /// Waiting for a trigger happens on the host: the GPIOs are polled
+    /// every 200 μs over the instrument link, with the GIL released in
+    /// between, so the latency from the edge to the next operation is in
+    /// the order of a millisecond and pulses shorter than the polling
+    /// interval may be missed altogether.
+    ///
+    /// >>> # wait for the laser to fire on GPIO 8 and then read
+    /// >>> arc.wait_for(WaitFor.Trigger(8, TriggerEdge.Rising), 5000)
+    /// >>> current = arc.read_one(3, 20, 0.2)
+    ///
+    /// :param condition: A variant of :class:`~pyarc2.WaitFor`
+    /// :param int timeout: Maximum time to wait for a trigger in milliseconds;
+    ///                     ``None`` to wait indefinitely
+    /// :raises ValueError: If ``condition`` is an iteration count or the trigger
+    ///                     GPIO belongs to an output cluster
+    /// :raises TimeoutError: If the trigger does not arrive within ``timeout``
+    #[pyo3(signature = (condition, timeout=None))]
+    fn wait_for<'py>(mut slf: PyRefMut<'py, Self>, py: Python<'py>, condition: PyWaitFor,
+        timeout: Option<u64>) -> PyResult<PyRefMut<'py, Self>> {
+
+        match condition._inner {
+            WaitCondition::Native(WaitFor::Time(duration)) => {
+                slf._instrument.add_delay(duration.as_nanos()).map_err(ArC2Error::new_exception)?;
+            },
+            WaitCondition::Native(_) | WaitCondition::Current { .. } => {
+                return Err(exceptions::PyValueError::new_err(
+                    "Only time and trigger conditions can be used to pause a sequence"));
+            },
+            WaitCondition::Trigger(pin, edge) => {
+                if let Some((_, dirs)) = &slf._periph.logic {
+                    if dirs[pin / 8] != IODir::IN {
+                        return Err(exceptions::PyValueError::new_err(
+                            format!("GPIO {} cannot be used as a trigger input as cluster {} \
+                                (GPIO {}-{}) is an output", pin, pin / 8, (pin / 8) * 8,
+                                (pin / 8) * 8 + 7)));
+                    }
+                }
 
-        let actual_cl3 = match cl3 {
-            Some(x) => x._inner,
-            None => IODir::OUT
-        };
+                slf._instrument.execute().map_err(ArC2Error::new_exception)?;
+                slf._instrument.wait();
+
+                let start = std::time::Instant::now();
+                let limit = timeout.map(std::time::Duration::from_millis);
+                let active = edge == TriggerEdge::Rising;
+                // an edge is a transition into the active level so the
+                // inactive level must be seen first
+                let mut armed = false;
+
+                loop {
+                    let level = (slf._instrument.read_logic().map_err(ArC2Error::new_exception)?
+                        >> pin) & 1 == 1;
+
+                    if level != active {
+                        armed = true;
+                    } else if armed {
+                        break;
+                    }
+
+                    if let Some(l) = limit {
+                        if start.elapsed() >= l {
+                            return Err(exceptions::PyTimeoutError::new_err(
+                                format!("No trigger received on GPIO {} within {} ms",
+                                    pin, l.as_millis())));
+                        }
+                    }
+
+                    // allow ctrl-c to interrupt an indefinite wait
+                    py.check_signals()?;
+                    py.allow_threads(|| std::thread::sleep(TRIGGER_POLL_INTERVAL));
+                }
+            }
+        }
 
-        match slf._instrument.set_logic(actual_cl0, actual_cl1, actual_cl2, actual_cl3, &mask) {
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
+        Ok(slf)
+    }
+
+    /// declare_peripheral_level(self, level, /)
+    /// --
+    ///
+    /// Declare the logic level expected by the peripheral connected to the
+    /// GPIOs. Once declared, :meth:`~pyarc2.Instrument.set_logic_level` and
+    /// :meth:`~pyarc2.Instrument.set_pins` will refuse to operate at a
+    /// different logic level. Use ``None`` to clear the declaration.
+    ///
+    /// :param level: A variant of :class:`~pyarc2.LogicLevel` or ``None``
+    /// :raises ValueError: If the logic level already configured differs from
+    ///                     ``level``
+    #[pyo3(signature = (level))]
+    fn declare_peripheral_level(&mut self, level: Option<PyLogicLevel>) -> PyResult<()> {
+        let level: Option<LogicLevel> = level.map(|l| l.into());
+        let previous = std::mem::replace(&mut self._gpio.peripheral_level, level);
+
+        if let Some(current) = &self._periph.logic_level {
+            if let Err(err) = self.check_peripheral_level(current) {
+                self._gpio.peripheral_level = previous;
+                return Err(err);
+            }
         }
+
+        Ok(())
     }
 
     /// set_logic_level(self, level, /)
@@ -1455,8 +5655,14 @@ impl PyInstrument {
     fn set_logic_level<'py>(mut slf: PyRefMut<'py, Self>, level: PyLogicLevel)
         -> PyResult<PyRefMut<'py, Self>> {
 
-        match slf._instrument.set_logic_level(level.into()) {
-            Ok(_) => Ok(slf),
+        let level: LogicLevel = level.into();
+        slf.check_peripheral_level(&level)?;
+
+        match slf._instrument.set_logic_level(level.clone()) {
+            Ok(_) => {
+                slf._periph.logic_level = Some(level);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
 
@@ -1470,16 +5676,78 @@ impl PyInstrument {
     ///
     /// :param chans: A list of analogue channel indices to change range
     /// :param rng: The range to set the channels to. Standard range is
-    ///             ±10 V, extended range is ±20 V.
+    ///             ±10 V, extended range is ±20 V. See also
+    ///             :meth:`~pyarc2.Instrument.set_auto_range`.
     fn set_channel_range<'py>(mut slf: PyRefMut<'py, Self>, chans: PyReadonlyArray1<'py, usize>, rng: PyOutputRange)
         -> PyResult<PyRefMut<'py, Self>> {
         let slice = chans.as_slice().unwrap();
-        match slf._instrument.set_channel_range(slice, &rng.into()) {
-            Ok(_) => Ok(slf),
+        let range: OutputRange = rng.into();
+        match slf._instrument.set_channel_range(slice, &range) {
+            Ok(_) => {
+                slf._channels.set_range(slice, range);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
     }
 
+    /// set_auto_range(self, enabled, /)
+    /// --
+    ///
+    /// Enable or disable automatic output range selection. Every voltage
+    /// applied by the library is validated against the output range of the
+    /// channel it is applied to, as tracked by
+    /// :meth:`~pyarc2.Instrument.channel_state`. A voltage beyond the
+    /// standard range (±10 V) on a ``STD`` channel normally raises a
+    /// ``ValueError``; with automatic range selection the channel is switched
+    /// to ``EXT`` right before the operation instead, once all voltages of
+    /// the operation have been validated. Channels are never switched back
+    /// to ``STD`` automatically. Every operation that needs a switch adds one
+    /// range change command to the command buffer; these are logged by
+    /// :meth:`~pyarc2.Instrument.range_switches`.
+    ///
+    /// :param bool enabled: Whether to switch ranges automatically
+    fn set_auto_range(&mut self, enabled: bool) {
+        self._auto_range = enabled;
+    }
+
+    /// auto_range(self, /)
+    /// --
+    ///
+    /// Whether automatic output range selection is enabled.
+    ///
+    /// :rtype: bool
+    fn auto_range(&self) -> bool {
+        self._auto_range
+    }
+
+    /// range_switches(self, reset=False, /)
+    /// --
+    ///
+    /// The automatic output range switches since the instrument was opened
+    /// or the log was last reset. Every entry is an additional range change
+    /// command that was queued right before an operation, and contains the
+    /// channels it switched to ``EXT``; the length of the list is the
+    /// number of extra commands automatic range selection has added to the
+    /// command buffer.
+    ///
+    /// >>> arc.set_auto_range(True)
+    /// >>> arc.pulse_one(3, 20, 12.0, 1000).execute()
+    /// >>> arc.range_switches()
+    /// [[20]]
+    ///
+    /// :param bool reset: Clear the log after reading it
+    /// :return: A list with the switched channels of every range change
+    /// :rtype: list of lists of int
+    #[pyo3(signature = (reset=false))]
+    fn range_switches(&mut self, reset: bool) -> Vec<Vec<usize>> {
+        if reset {
+            std::mem::take(&mut self._range_switches)
+        } else {
+            self._range_switches.clone()
+        }
+    }
+
     /// currents_from_address(self, addr, channels, /)
     /// --
     ///
@@ -1565,10 +5833,35 @@ impl PyInstrument {
         pw_nanos: u128, inter_nanos: u128, num_pulses: usize,
         read_at: PyReadAt, read_after: PyReadAfter) -> PyResult<PyRefMut<'py, Self>> {
 
+        // count the ramp steps and check the largest voltage of the ramp
+        // against all pulses; this is a conservative estimate of the dose
+        let nsteps = if vstep == 0.0 {
+            1
+        } else {
+            ramp_steps(vstart, vstep, vstop) as u64
+        };
+        let vpeak = if vstart.abs() > vstop.abs() { vstart } else { vstop };
+        let exposure = slf.check_pulses(&[high], &[device_key(low, high)], vpeak, pw_nanos,
+            nsteps * (num_pulses as u64))?;
+        let vread = match read_at._inner {
+            ReadAt::Arb(v) => Some(v),
+            _ => None
+        };
+        if let Some(v) = vread {
+            slf.check_voltage(low, v)?;
+        }
+        slf.queue_ranges(std::iter::once((high, vpeak)).chain(vread.map(|v| (low, v))))?;
+
         match slf._instrument.generate_ramp(low, high, vstart, vstep, vstop,
             pw_nanos, inter_nanos, num_pulses, read_at.into(),
             read_after.into()) {
-            Ok(_) => Ok(slf),
+            Ok(_) => {
+                slf.charge(exposure);
+                let vlast = ramp_value(vstart, vstep, nsteps as usize - 1);
+                slf.record_applied(std::iter::once((high, vlast)).chain(vread.map(|v| (low, -v))));
+                slf._channels.soft_ground(&[low, high]);
+                Ok(slf)
+            },
             Err(err) => Err(ArC2Error::new_exception(err))
         }
 
@@ -1609,61 +5902,577 @@ impl PyInstrument {
                 None => vec![]
             };
 
+            for chan in &low_chans {
+                slf.check_voltage(*chan, vread)?;
+            }
+            slf.queue_ranges(low_chans.iter().map(|c| (*c, vread)))?;
+
             match slf._instrument.generate_read_train(&low_chans, high_chans,
                 vread, nreads, inter_nanos, ground) {
 
-                Ok(_) => Ok(slf),
+                Ok(_) => {
+                    slf.record_applied(low_chans.iter().map(|c| (*c, -vread)));
+                    slf._channels.soft_ground(&low_chans);
+                    Ok(slf)
+                },
                 Err(err) => Err(ArC2Error::new_exception(err))
 
             }
     }
 
-    /// generate_vread_train(self, uchans, averaging, /)
+    /// generate_vread_train(self, uchans, averaging, /)
+    /// --
+    ///
+    /// Initiate a voltage read train. This will queue instructions that will read
+    /// voltages from all ``uchans`` channels. Results must be retrieved with an
+    /// iterator as described in :meth:`~pyarc2.Instrument.generate_ramp`.
+    ///
+    /// :param uchans: An array of unsinged integers to read voltages from
+    /// :param bool averaging: Whether to perform averaged (``True``) or one-shot reads
+    ///                        (``False``).
+    fn generate_vread_train<'py>(mut slf: PyRefMut<'py, Self>,
+        uchans: PyReadonlyArray1<'py, usize>, averaging: bool,
+        npulses: usize, inter_nanos: u128) -> PyResult<PyRefMut<'py, Self>> {
+
+        let chans = uchans.as_slice().unwrap();
+
+        match slf._instrument.generate_vread_train(chans, averaging, npulses,
+            inter_nanos) {
+
+            Ok(_) => Ok(slf),
+            Err(err) => Err(ArC2Error::new_exception(err))
+        }
+    }
+
+    /// read_train(self, low, high, vread, interpulse, preload, condition, baseline=False, /)
+    /// --
+    ///
+    /// Perform a retention-like operation based on subsequent number of read
+    /// pulses which can be separated by `interpulse` nanoseconds.
+    ///
+    /// When ``condition`` is :meth:`pyarc2.WaitFor.Current` the train is
+    /// queued in chunks and every read-out is compared against the threshold
+    /// as soon as it is available; the train stops on the first read that
+    /// crosses it. In that case the reads are consumed by the library and the
+    /// index of the crossing read is returned instead. Between chunks the
+    /// interval between reads may be longer than ``interpulse``. All
+    /// channels are grounded once the train ends, including when it fails
+    /// or is interrupted with Ctrl-C.
+    ///
+    /// With ``baseline`` a reference read with all channels at 0.0 V is
+    /// taken on ``high`` in the same command buffer, right before the train
+    /// starts, and subtracted from every read before comparing against the
+    /// threshold. This is only available for current conditions, as for
+    /// all other conditions the records are retrieved unprocessed with
+    /// :meth:`~pyarc2.Instrument.pick_one`.
+    ///
+    /// >>> # read every 10 μs until the device switches past 100 μA
+    /// >>> idx = arc.read_train(5, 20, 0.2, 10000, None,
+    /// ...     WaitFor.Current(100e-6, True, 100000))
+    ///
+    /// :param int low: The low voltage channel (typ. grounded)
+    /// :param int high: The high voltage channel
+    /// :param float vread: Read-out voltage
+    /// :param int interpulse: Delay between consecutive read-outs in nanoseconds
+    /// :param float preload: Voltage to apply to the high channel before the
+    ///                       first read or ``None``
+    /// :param condition: Variant of :class:`pyarc2.WaitFor` denoting the termination
+    ///                   condition for this read train
+    /// :param bool baseline: Take a zero-bias reference read
+    /// :return: For current conditions the (zero-based) index of the read that
+    ///          crossed the threshold or ``None`` if the read limit was reached;
+    ///          ``None`` for all other conditions
+    /// :raises ValueError: If ``condition`` is a trigger condition or
+    ///                     ``baseline`` is used with a condition other than
+    ///                     :meth:`~pyarc2.WaitFor.Current`
+    #[pyo3(signature = (low, high, vread, interpulse, preload, condition, baseline=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn read_train<'py>(mut slf: PyRefMut<'py, Self>, py: Python<'py>, low: usize, high: usize,
+        vread: f32, interpulse: u64, preload: Option<f32>, condition: PyWaitFor, baseline: bool)
+        -> PyResult<Option<usize>> {
+
+        if baseline && !matches!(condition._inner, WaitCondition::Current { .. }) {
+            return Err(exceptions::PyValueError::new_err(
+                "A baseline can only be subtracted from read trains with a current condition"));
+        }
+
+        slf.check_voltage(low, vread)?;
+        if let Some(v) = preload {
+            slf.check_voltage(high, v)?;
+        }
+        slf.queue_ranges(std::iter::once((low, vread)).chain(preload.map(|v| (high, v))))?;
+
+        let (threshold, above, max_reads) = match condition._inner {
+            WaitCondition::Current { threshold, above, max_reads } => (threshold, above, max_reads),
+            _ => {
+                let condition: WaitFor = condition.try_into()?;
+
+                return match slf._instrument.read_train(low, high, vread, interpulse as u128,
+                    preload, condition) {
+                    Ok(_) => {
+                        slf.record_applied(preload.map(|v| (high, v))
+                            .into_iter().chain(std::iter::once((low, -vread))));
+                        slf._channels.soft_ground(&[low, high]);
+                        Ok(None)
+                    },
+                    Err(err) => Err(ArC2Error::new_exception(err))
+                };
+            }
+        };
+
+        // the channels must be grounded even if the train fails or is
+        // interrupted, otherwise they are left biased
+        let result = slf.current_train(py, low, high, vread, interpulse, preload, threshold,
+            above, max_reads, baseline);
+        let grounded = slf.execute_grounded();
+
+        let index = result?;
+        grounded?;
+
+        Ok(index)
+    }
+
+    /// set_calibration(self, calibration, /)
+    /// --
+    ///
+    /// Apply a :class:`~pyarc2.Calibration` to all current read-outs,
+    /// including :meth:`~pyarc2.Instrument.read_one`, the other ``read_*``
+    /// and ``pulseread_*`` methods, :meth:`~pyarc2.Instrument.mac` and
+    /// current records retrieved with :meth:`~pyarc2.Instrument.pick_one`.
+    /// Raw memory access methods (``*_from_address``) are not corrected.
+    /// Calibrations are measured in the standard output range and are not
+    /// valid in the extended range, so read-outs of channels that are in
+    /// ``OutputRange.EXT`` at the time they are retrieved are left as is.
+    ///
+    /// :param calibration: A :class:`~pyarc2.Calibration` or ``None`` to
+    ///                     remove the current calibration
+    fn set_calibration(&mut self, calibration: Option<PyCalibration>) {
+        self._calibration = calibration;
+    }
+
+    /// calibration(self, /)
+    /// --
+    ///
+    /// The calibration applied to current read-outs.
+    ///
+    /// :return: A :class:`~pyarc2.Calibration` or ``None``
+    fn calibration(&self) -> Option<PyCalibration> {
+        self._calibration.clone()
+    }
+
+    /// set_correction(self, enabled, /)
+    /// --
+    ///
+    /// Enable or disable calibration correction without removing the
+    /// calibration. Use this to retrieve raw data. Correction is enabled by
+    /// default.
+    ///
+    /// >>> arc.set_correction(False)
+    /// >>> raw = arc.read_one(3, 20, 0.2)
+    /// >>> arc.set_correction(True)
+    ///
+    /// :param bool enabled: Whether to correct current read-outs
+    fn set_correction(&mut self, enabled: bool) {
+        self._correction = enabled;
+    }
+
+    /// correction(self, /)
+    /// --
+    ///
+    /// Whether calibration correction is enabled.
+    ///
+    /// :rtype: bool
+    fn correction(&self) -> bool {
+        self._correction
+    }
+
+    /// measure_calibration(self, loads=None, vread=0.2, nreads=16, /)
+    /// --
+    ///
+    /// Measure a new calibration. The zero-bias offset of every channel is
+    /// measured first with all channels held at 0.0 V, so nothing that could
+    /// conduct current should be connected apart from the calibration loads.
+    /// Then, for every channel in ``loads``, the current through a known
+    /// resistor connected between that channel and a bias channel is read
+    /// at ``vread`` and the gain is the ratio of the expected to the measured
+    /// current. Channels without a load keep unity gain. Every measurement is
+    /// averaged over ``nreads`` reads. The offsets are measured in a command
+    /// buffer of their own and the loads in as few command buffers as the
+    /// output memory allows (``256 // nreads`` loads per buffer), with all
+    /// channels grounded in between. The calibration is returned but not
+    /// applied; use :meth:`~pyarc2.Instrument.set_calibration`. All channels
+    /// must be in the standard output range as calibrations only apply to
+    /// that range.
+    ///
+    /// >>> # 100 kΩ resistors between channels 0-15 and 16-31
+    /// >>> cal = arc.measure_calibration({16 + i: (i, 100e3) for i in range(16)})
+    /// >>> cal.save('arc2-unit3.json')
+    ///
+    /// :param loads: A dict of ``{read channel: (bias channel, resistance)}``
+    ///               or ``None`` to only measure offsets
+    /// :param float vread: The voltage to read the loads at
+    /// :param int nreads: Number of reads to average per measurement; at
+    ///                    most 256
+    /// :return: A new :class:`~pyarc2.Calibration`
+    /// :raises ValueError: If ``nreads`` is zero or above 256, a channel is out of range
+    ///                     or in the extended output range, a load
+    ///                     resistance is not positive or a measured current
+    ///                     is zero
+    #[pyo3(signature = (loads=None, vread=0.2, nreads=16))]
+    fn measure_calibration(&mut self, loads: Option<BTreeMap<usize, (usize, f32)>>,
+        vread: f32, nreads: usize) -> PyResult<PyCalibration> {
+
+        if nreads == 0 || nreads > BUFFER_READS {
+            return Err(exceptions::PyValueError::new_err(
+                format!("nreads must be between 1 and {}, got {}", BUFFER_READS, nreads)));
+        }
+
+        let loads = loads.unwrap_or_default();
+        check_channels(loads.keys().chain(loads.values().map(|(bias, _)| bias)))?;
+
+        if let Some(chan) = (0..64).find(|c| self._channels.chans[*c].range != OutputRange::STD) {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Channel {} is in the extended range; calibrations can only be \
+                    measured with all channels in the standard range", chan)));
+        }
+
+        for (chan, (bias, ohms)) in &loads {
+            if ohms.is_nan() || *ohms <= 0.0 {
+                return Err(exceptions::PyValueError::new_err(
+                    format!("Load between channels {} and {} must have a positive resistance",
+                        bias, chan)));
+            }
+            self.check_voltage(*bias, vread)?;
+        }
+
+        let all: Vec<usize> = (0..64).collect();
+
+        // raw read-outs; the current calibration must not be applied
+        let mean = |reads: &[Vec<f32>], idx: usize| -> f32 {
+            reads.iter().map(|r| r[idx]).sum::<f32>() / (nreads as f32)
+        };
+
+        for _ in 0..nreads {
+            self.queue_reference(&all)?;
+        }
+        self.execute_grounded()?;
+
+        let zero = self.collect(nreads, &all, ReadType::Current)?;
+        let offsets: Vec<f32> = (0..64).map(|c| mean(&zero, c)).collect();
+        let mut gains = vec![1.0f32; 64];
+
+        let loads: Vec<(usize, (usize, f32))> = loads.into_iter().collect();
+
+        for group in loads.chunks(BUFFER_READS / nreads) {
+            for (chan, (bias, _)) in group {
+                for _ in 0..nreads {
+                    self.queue_read_one(*bias, *chan, vread)?;
+                }
+            }
+            self.execute_grounded()?;
+
+            // drain the whole group before checking any of its loads
+            let mut currents = Vec::with_capacity(group.len());
+            for (chan, _) in group {
+                currents.push(mean(&self.collect(nreads, &[*chan], ReadType::Current)?, 0));
+            }
+
+            for ((chan, (bias, ohms)), current) in group.iter().zip(currents) {
+                let measured = (current - offsets[*chan]).abs();
+                if measured == 0.0 {
+                    return Err(exceptions::PyValueError::new_err(
+                        format!("No current measured through the load between channels {} \
+                            and {}", bias, chan)));
+                }
+                gains[*chan] = (vread / ohms).abs() / measured;
+            }
+        }
+
+        PyCalibration::validate(CalibrationData { offsets, gains })
+    }
+
+    /// self_test(self, vtest=1.0, vtol=0.05, itol=1e-8, loads=None, vread=0.2, rtol=0.05, /)
+    /// --
+    ///
+    /// Check every channel of the instrument. All channels are driven at
+    /// ``vtest`` and ``-vtest`` and their voltages read back, which must be
+    /// within ``vtol`` of the programmed value. All channels are then held at
+    /// 0.0 V and the current of every channel, which must not exceed
+    /// ``itol``, is read. As all channels are at the same voltage in every
+    /// step this can be run with a device connected. Optionally the channels
+    /// in ``loads`` are also checked against known resistors: the current
+    /// through each load at ``vread`` must be within ``rtol`` (relative) of
+    /// the expected value. Everything is run in a single command buffer and
+    /// all channels are grounded afterwards.
+    ///
+    /// >>> report = arc.self_test()
+    /// >>> if not report.passed:
+    /// ...     print(report.failures)
+    ///
+    /// :param float vtest: The test voltage
+    /// :param float vtol: Voltage tolerance in V
+    /// :param float itol: Zero-bias current tolerance in A
+    /// :param loads: A dict of ``{read channel: (bias channel, resistance)}``
+    ///               or ``None``
+    /// :param float vread: The voltage to read the loads at
+    /// :param float rtol: Relative tolerance of load currents
+    /// :return: A :class:`~pyarc2.SelfTestReport`
+    /// :raises ValueError: If a channel in ``loads`` is out of range
+    #[pyo3(signature = (vtest=1.0, vtol=0.05, itol=1e-8, loads=None, vread=0.2, rtol=0.05))]
+    #[allow(clippy::too_many_arguments)]
+    fn self_test(&mut self, vtest: f32, vtol: f32, itol: f32,
+        loads: Option<BTreeMap<usize, (usize, f32)>>, vread: f32, rtol: f32)
+        -> PyResult<PySelfTestReport> {
+
+        let all: Vec<usize> = (0..64).collect();
+        let loads = loads.unwrap_or_default();
+        check_channels(loads.keys().chain(loads.values().map(|(bias, _)| bias)))?;
+
+        for chan in &all {
+            self.check_voltage(*chan, vtest)?;
+        }
+        for (bias, _) in loads.values() {
+            self.check_voltage(*bias, vread)?;
+        }
+        self.queue_ranges(all.iter().map(|c| (*c, vtest)))?;
+
+        let levels = [vtest, -vtest];
+        for v in &levels {
+            self._instrument.config_channels(&[], Some(*v)).map_err(ArC2Error::new_exception)?;
+            self._instrument.vread_channels_deferred(&all, true).map_err(ArC2Error::new_exception)?;
+        }
+        self._instrument.config_channels(&[], Some(0.0)).map_err(ArC2Error::new_exception)?;
+        self._instrument.read_slice_open_deferred(&all, false).map_err(ArC2Error::new_exception)?;
+        for (chan, (bias, _)) in &loads {
+            self.queue_read_one(*bias, *chan, vread)?;
+        }
+
+        self._instrument.ground_all().map_err(ArC2Error::new_exception)?;
+        self._channels.ground_all();
+        self._instrument.execute().map_err(ArC2Error::new_exception)?;
+        self._instrument.wait();
+
+        let mut reports: Vec<PyChannelReport> = all.iter().map(|c| PyChannelReport {
+            channel: *c,
+            voltage_error: 0.0,
+            leakage: 0.0,
+            load_error: None,
+            issues: vec![]
+        }).collect();
+
+        for v in &levels {
+            let measured = self.collect_voltages(1, &all)?.remove(0);
+            for (report, vm) in reports.iter_mut().zip(measured.iter()) {
+                let err = (vm - v).abs();
+                report.voltage_error = report.voltage_error.max(err);
+                if err > vtol {
+                    report.issues.push(format!("measured {} V when driven at {} V", vm, v));
+                }
+            }
+        }
+
+        let leakage = self.collect_reads(1, &all)?.remove(0);
+        for (report, current) in reports.iter_mut().zip(leakage.iter()) {
+            report.leakage = *current;
+            if current.abs() > itol {
+                report.issues.push(format!("{:.3e} A at zero bias", current));
+            }
+        }
+
+        for (chan, (bias, ohms)) in &loads {
+            let current = self.collect_reads(1, &[*chan])?[0][0];
+            let expected = vread / ohms;
+            let err = (current.abs() - expected.abs()).abs() / expected.abs();
+            let report = &mut reports[*chan];
+            report.load_error = Some(err);
+            if err.is_nan() || err > rtol {
+                report.issues.push(format!("{:.3e} A through the {} Ω load from channel {}; \
+                    expected {:.3e} A", current, ohms, bias, expected));
+            }
+        }
+
+        Ok(PySelfTestReport { channels: reports })
+    }
+
+    /// scan_shorts_opens(self, threshold_low, threshold_high, vread=0.2, order=BiasOrder.Rows, /)
+    /// --
+    ///
+    /// Read every crosspoint and classify it as open, short or OK based on
+    /// the magnitude of its current. Crosspoints conducting
+    /// ``threshold_low`` or less are considered open (for instance a broken
+    /// wire-bond or a probe not touching its pad) and crosspoints conducting
+    /// ``threshold_high`` or more are considered shorted.
+    ///
+    /// >>> report = arc.scan_shorts_opens(1e-9, 1e-3)
+    /// >>> report.shorts
+    /// [(3, 20)]
+    ///
+    /// :param float threshold_low: Current at or below which a crosspoint is open
+    /// :param float threshold_high: Current at or above which a crosspoint is shorted
+    /// :param float vread: The read-out voltage
+    /// :param order: A variant of :class:`pyarc2.BiasOrder`
+    /// :return: A :class:`~pyarc2.CrosspointReport`
+    /// :raises ValueError: If ``threshold_low`` is not below ``threshold_high``
+    #[pyo3(signature = (threshold_low, threshold_high, vread=0.2, order=None))]
+    fn scan_shorts_opens<'py>(&mut self, py: Python<'py>, threshold_low: f32, threshold_high: f32,
+        vread: f32, order: Option<PyBiasOrder>) -> PyResult<PyCrosspointReport> {
+
+        if threshold_low.is_nan() || threshold_high.is_nan() || threshold_low >= threshold_high {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Open threshold ({} A) must be lower than the short threshold ({} A)",
+                    threshold_low, threshold_high)));
+        }
+
+        let order = order.unwrap_or(PyBiasOrder { _inner: BiasOrder::Rows });
+        let grid = self.read_all(py, vread, order, false)?;
+        let currents: Vec<f32> = grid.readonly().as_array().iter().copied().collect();
+
+        let bitlines = crossing_channels(16);
+        let wordlines = crossing_channels(0);
+        let mut shorts = vec![];
+        let mut opens = vec![];
+
+        for (idx, current) in currents.iter().enumerate() {
+            let device = (bitlines[idx / 32], wordlines[idx % 32]);
+            if current.abs() >= threshold_high {
+                shorts.push(device);
+            } else if current.abs() <= threshold_low {
+                opens.push(device);
+            }
+        }
+
+        Ok(PyCrosspointReport { currents, shorts, opens })
+    }
+
+    /// channel_state(self, /)
+    /// --
+    ///
+    /// Get the state of all 64 channels as tracked by the library. The state
+    /// is updated as commands are queued, so it reflects the configuration
+    /// ArC TWO will be in once the command buffer has been executed. Channel
+    /// configuration, grounding and range commands are tracked exactly;
+    /// composite operations such as reads, pulses and ramps are assumed to
+    /// leave the channels they use in arbitrary voltage operation at 0.0 V.
+    ///
+    /// >>> arc.config_channels([(3, 1.0)], None).gnd_add([5]).execute()
+    /// >>> states = arc.channel_state()
+    /// >>> states[3].voltage
+    /// 1.0
+    /// >>> states[5].mode
+    /// ChannelMode<HardGnd>
+    ///
+    /// :return: A list of 64 :class:`~pyarc2.ChannelState` objects in
+    ///          channel order
+    fn channel_state(&self) -> Vec<PyChannelState> {
+        self._channels.chans.iter().enumerate()
+            .map(|(idx, state)| PyChannelState::new(idx, state))
+            .collect()
+    }
+
+    /// snapshot(self, /)
+    /// --
+    ///
+    /// Capture the current configuration of the instrument, as tracked by the
+    /// library, into a :class:`~pyarc2.Snapshot`. This can later be used with
+    /// :meth:`~pyarc2.Instrument.restore` to return to the same bias state,
+    /// for instance after a diagnostic read in the middle of an experiment.
+    ///
+    /// >>> snap = arc.snapshot()
+    /// >>> current = arc.read_one(3, 20, 0.2)
+    /// >>> arc.restore(snap).execute()
+    ///
+    /// :rtype: :class:`~pyarc2.Snapshot`
+    fn snapshot(&self) -> PySnapshot {
+        PySnapshot { _inner: self.config_snapshot() }
+    }
+
+    /// restore(self, snapshot, /)
+    /// --
+    ///
+    /// Queue the commands required to bring the instrument back to the
+    /// configuration captured in ``snapshot``. Only the parts of the
+    /// configuration that differ from the current tracked state are
+    /// reprogrammed. Channels that were in high speed mode are restored as
+    /// arbitrary voltage channels at their last programmed voltage. An
+    /// :meth:`~pyarc2.Instrument.execute` is required to actually apply the
+    /// configuration. Restored channel and AUX DAC voltages are checked
+    /// against the safety limits and nothing is queued if any of them fails.
+    ///
+    /// :param snapshot: A :class:`~pyarc2.Snapshot` to restore
+    /// :raises SafetyLimitError: If a restored voltage violates the safety limits
+    /// :raises ValueError: If the snapshot contains invalid values
+    fn restore<'py>(mut slf: PyRefMut<'py, Self>, snapshot: PyRef<'py, PySnapshot>)
+        -> PyResult<PyRefMut<'py, Self>> {
+        slf.queue_restore(&snapshot._inner)?;
+        Ok(slf)
+    }
+
+    /// set_safety_limits(self, limits, /)
+    /// --
+    ///
+    /// Replace the safety limits of this instrument. All subsequent operations
+    /// will be checked against ``limits`` before being queued. Accumulated
+    /// device exposure is retained; use
+    /// :meth:`~pyarc2.Instrument.reset_exposure` to clear it.
+    ///
+    /// :param limits: An instance of :class:`~pyarc2.SafetyLimits`
+    fn set_safety_limits(&mut self, limits: PySafetyLimits) {
+        self._limits = limits;
+    }
+
+    /// safety_limits(self, /)
+    /// --
+    ///
+    /// Get a copy of the current safety limits.
+    ///
+    /// :rtype: :class:`~pyarc2.SafetyLimits`
+    fn safety_limits(&self) -> PySafetyLimits {
+        self._limits.clone()
+    }
+
+    /// arm(self, state=True, /)
     /// --
     ///
-    /// Initiate a voltage read train. This will queue instructions that will read
-    /// voltages from all ``uchans`` channels. Results must be retrieved with an
-    /// iterator as described in :meth:`~pyarc2.Instrument.generate_ramp`.
+    /// Arm (or disarm) the instrument. Operations above the ``soft_voltage``
+    /// of the configured :class:`~pyarc2.SafetyLimits` are only allowed while
+    /// the instrument is armed. Hard limits are always enforced.
     ///
-    /// :param uchans: An array of unsinged integers to read voltages from
-    /// :param bool averaging: Whether to perform averaged (``True``) or one-shot reads
-    ///                        (``False``).
-    fn generate_vread_train<'py>(mut slf: PyRefMut<'py, Self>,
-        uchans: PyReadonlyArray1<'py, usize>, averaging: bool,
-        npulses: usize, inter_nanos: u128) -> PyResult<PyRefMut<'py, Self>> {
-
-        let chans = uchans.as_slice().unwrap();
-
-        match slf._instrument.generate_vread_train(chans, averaging, npulses,
-            inter_nanos) {
+    /// :param bool state: Whether to arm or disarm the instrument
+    #[pyo3(signature = (state=true))]
+    fn arm<'py>(mut slf: PyRefMut<'py, Self>, state: bool) -> PyRefMut<'py, Self> {
+        slf._armed = state;
+        slf
+    }
 
-            Ok(_) => Ok(slf),
-            Err(err) => Err(ArC2Error::new_exception(err))
-        }
+    /// armed(self, /)
+    /// --
+    ///
+    /// Returns ``True`` if the instrument is armed.
+    fn armed(&self) -> bool {
+        self._armed
     }
 
-    /// read_train(self, low, high, vread, interpulse, condition, /)
+    /// device_exposure(self, low, high, /)
     /// --
     ///
-    /// Perform a retention-like operation based on subsequent number of read
-    /// pulses which can be separated by `interpulse` nanoseconds.
+    /// Get the number of pulses and the cumulative dose (in V²·s) applied to
+    /// the crosspoint between ``low`` and ``high`` since the instrument was
+    /// opened or the exposure was last reset.
     ///
-    /// :param int low: The low voltage channel (typ. grounded)
+    /// :param int low: The low voltage channel
     /// :param int high: The high voltage channel
-    /// :param float vread: Read-out voltage
-    /// :param int interpulse: Delay between consecutive read-outs in nanoseconds
-    /// :param condition: Variant of :class:`pyarc2.WaitFor` denoting the termination
-    ///                   condition for this read train
-    #[pyo3(signature = (low, high, vread, interpulse, preload, condition))]
-    fn read_train<'py>(mut slf: PyRefMut<'py, Self>, low: usize, high: usize,
-        vread: f32, interpulse: u64, preload: Option<f32>, condition: PyWaitFor)
-        -> PyResult<()> {
+    /// :return: A tuple of ``(pulses, dose)``
+    fn device_exposure(&self, low: usize, high: usize) -> (u64, f64) {
+        let exposure = self._exposure.get(&device_key(low, high)).copied().unwrap_or_default();
+        (exposure.pulses, exposure.dose)
+    }
 
-        match slf._instrument.read_train(low, high, vread, interpulse as u128,
-            preload, condition.into()) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(ArC2Error::new_exception(err))
-        }
+    /// reset_exposure(self, /)
+    /// --
+    ///
+    /// Clear the accumulated pulse count and dose of all devices.
+    fn reset_exposure(&mut self) {
+        self._exposure.clear();
     }
 
     /// pick_one(self, mode, /)
@@ -1682,13 +6491,22 @@ impl PyInstrument {
     fn pick_one<'py>(&mut self, py: Python<'py>, mode: PyDataMode, rtype: PyReadType) ->
         PyResult<Option<Bound<'py, PyArray<f32, Ix1>>>> {
 
+        let chans: Vec<usize> = match mode._inner {
+            DataMode::All => (0..64).collect(),
+            DataMode::Words => crossing_channels(0),
+            DataMode::Bits => crossing_channels(16)
+        };
+        let current = matches!(rtype._inner, ReadType::Current);
         let mode: DataMode = mode.into();
         let rtype: ReadType = rtype.into();
 
         match self._instrument.pick_one(mode, rtype) {
             Ok(data_opt) => {
                 match data_opt {
-                    Some(data) => {
+                    Some(mut data) => {
+                        if current {
+                            self.correct(&chans, &mut data);
+                        }
                         let array = data.into_pyarray_bound(py);
                         Ok(Some(array))
                     },
@@ -1702,6 +6520,603 @@ impl PyInstrument {
 
 }
 
+/// Serialise a dict into JSON; values JSON does not support are stored
+/// by their string representation
+fn json_dumps(py: Python, value: &Bound<'_, PyDict>) -> PyResult<String> {
+    let kwargs = PyDict::new_bound(py);
+    kwargs.set_item("default", py.import_bound("builtins")?.getattr("str")?)?;
+    py.import_bound("json")?
+        .call_method("dumps", (value,), Some(&kwargs))?
+        .extract()
+}
+
+const MAP_CSV_TAG: &str = "# pyarc2 map v1";
+const TRACE_CSV_TAG: &str = "# pyarc2 trace v1";
+const CSV_METADATA: &str = "# metadata: ";
+const TRACE_CSV_HEADER: &str = "time,current";
+
+enum ExportFormat { Npz, Csv }
+
+impl ExportFormat {
+    fn from_path(path: &str) -> PyResult<ExportFormat> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".npz") {
+            Ok(ExportFormat::Npz)
+        } else if lower.ends_with(".csv") {
+            Ok(ExportFormat::Csv)
+        } else {
+            Err(exceptions::PyValueError::new_err(
+                format!("{}: unknown format, use a .npz or .csv extension", path)))
+        }
+    }
+}
+
+fn export_io_error<E: std::fmt::Display>(path: &str, err: E) -> PyErr {
+    exceptions::PyIOError::new_err(format!("{}: {}", path, err))
+}
+
+fn export_format_error(path: &str, msg: &str) -> PyErr {
+    exceptions::PyValueError::new_err(format!("{}: {}", path, msg))
+}
+
+fn export_metadata(py: Python, metadata: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    match metadata {
+        Some(m) => json_dumps(py, m),
+        None => Ok("{}".to_string())
+    }
+}
+
+/// Split a pyarc2 CSV file into its metadata and data lines, checking the tag
+fn read_csv_export(path: &str, tag: &str) -> PyResult<(String, Vec<String>)> {
+    let contents = std::fs::read_to_string(path).map_err(|err| export_io_error(path, err))?;
+    let mut lines = contents.lines();
+
+    if lines.next().map(|l| l.trim_end()) != Some(tag) {
+        return Err(export_format_error(path, &format!("missing \"{}\" header", tag)));
+    }
+
+    let mut metadata = "{}".to_string();
+    let mut data = vec![];
+    for line in lines {
+        if let Some(m) = line.strip_prefix(CSV_METADATA) {
+            metadata = m.to_string();
+        } else if !line.starts_with('#') && !line.trim().is_empty() {
+            data.push(line.to_string());
+        }
+    }
+
+    Ok((metadata, data))
+}
+
+fn parse_csv_value<T: std::str::FromStr>(path: &str, value: &str) -> PyResult<T> {
+    value.trim().parse::<T>()
+        .map_err(|_| export_format_error(path, &format!("invalid value \"{}\"", value.trim())))
+}
+
+fn parse_csv_row<T: std::str::FromStr>(path: &str, line: &str) -> PyResult<Vec<T>> {
+    line.split(',').map(|v| parse_csv_value(path, v)).collect()
+}
+
+/// Whether ``value`` is a :class:`pyarc2.Measurement`
+fn is_measurement(py: Python, value: &Bound<'_, PyAny>) -> PyResult<bool> {
+    let cls = py.import_bound("pyarc2._measurement")?.getattr("Measurement")?;
+    value.is_instance(&cls)
+}
+
+/// Build a :class:`pyarc2.Measurement` from loaded data
+fn export_measurement<'py>(py: Python<'py>, data: Bound<'py, PyAny>, metadata: &str,
+    times: Option<Bound<'py, PyAny>>) -> PyResult<Bound<'py, PyAny>> {
+
+    let meta = py.import_bound("json")?.call_method1("loads", (metadata,))?;
+    let kwargs = PyDict::new_bound(py);
+    if let Some(t) = times {
+        kwargs.set_item("times", t)?;
+    }
+    py.import_bound("pyarc2._measurement")?
+        .getattr("Measurement")?
+        .call((data, meta), Some(&kwargs))
+}
+
+fn write_map(path: &str, data: ndarray::ArrayView2<f32>, metadata: &str) -> PyResult<()> {
+    match ExportFormat::from_path(path)? {
+        ExportFormat::Npz => {
+            let file = std::fs::File::create(path).map_err(|err| export_io_error(path, err))?;
+            let mut npz = NpzWriter::new(file);
+            npz.add_array("data", &data).map_err(|err| export_io_error(path, err))?;
+            npz.add_array("metadata", &ndarray::arr1(metadata.as_bytes()))
+                .map_err(|err| export_io_error(path, err))?;
+            npz.finish().map_err(|err| export_io_error(path, err))?;
+        },
+        ExportFormat::Csv => {
+            let mut out = format!("{}\n{}{}\n", MAP_CSV_TAG, CSV_METADATA, metadata);
+            for row in data.rows() {
+                let values: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+                out.push_str(&values.join(","));
+                out.push('\n');
+            }
+            std::fs::write(path, out).map_err(|err| export_io_error(path, err))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_map(path: &str) -> PyResult<(Array2<f32>, String)> {
+    match ExportFormat::from_path(path)? {
+        ExportFormat::Npz => {
+            let file = std::fs::File::open(path).map_err(|err| export_io_error(path, err))?;
+            let mut npz = NpzReader::new(file).map_err(|err| export_io_error(path, err))?;
+            let data: Array2<f32> = npz.by_name("data")
+                .map_err(|err| export_io_error(path, err))?;
+            let metadata: ndarray::Array1<u8> = npz.by_name("metadata")
+                .map_err(|err| export_io_error(path, err))?;
+            let metadata = String::from_utf8(metadata.to_vec())
+                .map_err(|err| export_io_error(path, err))?;
+            Ok((data, metadata))
+        },
+        ExportFormat::Csv => {
+            let (metadata, lines) = read_csv_export(path, MAP_CSV_TAG)?;
+            let rows = lines.iter()
+                .map(|l| parse_csv_row::<f32>(path, l))
+                .collect::<PyResult<Vec<Vec<f32>>>>()?;
+            let ncols = rows.first().map(|r| r.len()).unwrap_or(0);
+            if rows.iter().any(|r| r.len() != ncols) {
+                return Err(export_format_error(path, "rows have different lengths"));
+            }
+            let nrows = rows.len();
+            let data = Array2::from_shape_vec((nrows, ncols), rows.into_iter().flatten().collect())
+                .map_err(|err| export_format_error(path, &format!("{}", err)))?;
+            Ok((data, metadata))
+        }
+    }
+}
+
+fn write_trace(path: &str, times: &[f64], currents: &[f32], metadata: &str) -> PyResult<()> {
+    match ExportFormat::from_path(path)? {
+        ExportFormat::Npz => {
+            let file = std::fs::File::create(path).map_err(|err| export_io_error(path, err))?;
+            let mut npz = NpzWriter::new(file);
+            npz.add_array("times", &ndarray::arr1(times))
+                .map_err(|err| export_io_error(path, err))?;
+            npz.add_array("currents", &ndarray::arr1(currents))
+                .map_err(|err| export_io_error(path, err))?;
+            npz.add_array("metadata", &ndarray::arr1(metadata.as_bytes()))
+                .map_err(|err| export_io_error(path, err))?;
+            npz.finish().map_err(|err| export_io_error(path, err))?;
+        },
+        ExportFormat::Csv => {
+            let mut out = format!("{}\n{}{}\n{}\n", TRACE_CSV_TAG, CSV_METADATA, metadata,
+                TRACE_CSV_HEADER);
+            for (t, i) in times.iter().zip(currents) {
+                out.push_str(&format!("{},{}\n", t, i));
+            }
+            std::fs::write(path, out).map_err(|err| export_io_error(path, err))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_trace(path: &str) -> PyResult<(Vec<f64>, Vec<f32>, String)> {
+    match ExportFormat::from_path(path)? {
+        ExportFormat::Npz => {
+            let file = std::fs::File::open(path).map_err(|err| export_io_error(path, err))?;
+            let mut npz = NpzReader::new(file).map_err(|err| export_io_error(path, err))?;
+            let times: ndarray::Array1<f64> = npz.by_name("times")
+                .map_err(|err| export_io_error(path, err))?;
+            let currents: ndarray::Array1<f32> = npz.by_name("currents")
+                .map_err(|err| export_io_error(path, err))?;
+            let metadata: ndarray::Array1<u8> = npz.by_name("metadata")
+                .map_err(|err| export_io_error(path, err))?;
+            let metadata = String::from_utf8(metadata.to_vec())
+                .map_err(|err| export_io_error(path, err))?;
+            Ok((times.to_vec(), currents.to_vec(), metadata))
+        },
+        ExportFormat::Csv => {
+            let (metadata, lines) = read_csv_export(path, TRACE_CSV_TAG)?;
+            match lines.first() {
+                Some(header) if header.trim() == TRACE_CSV_HEADER => {},
+                _ => return Err(export_format_error(path,
+                    &format!("missing \"{}\" column header", TRACE_CSV_HEADER)))
+            }
+            let mut times = Vec::with_capacity(lines.len());
+            let mut currents = Vec::with_capacity(lines.len());
+            for line in lines.iter().skip(1) {
+                let mut fields = line.splitn(2, ',');
+                match (fields.next(), fields.next()) {
+                    (Some(t), Some(i)) => {
+                        times.push(parse_csv_value::<f64>(path, t)?);
+                        currents.push(parse_csv_value::<f32>(path, i)?);
+                    },
+                    _ => return Err(export_format_error(path,
+                        &format!("expected \"{}\", found \"{}\"", TRACE_CSV_HEADER, line)))
+                }
+            }
+            Ok((times, currents, metadata))
+        }
+    }
+}
+
+#[cfg(feature = "arrow")]
+struct ArrowRow {
+    seq: u64,
+    timestamp: i64,
+    operation: String,
+    channels: Option<Vec<u32>>,
+    shape: Vec<u32>,
+    values: Vec<f32>,
+    metadata: Option<String>
+}
+
+#[cfg(feature = "arrow")]
+fn arrow_error(err: ArrowError) -> PyErr {
+    exceptions::PyIOError::new_err(format!("{}", err))
+}
+
+/// Stream measurement results into Apache Arrow IPC streams. Results are
+/// written under ``directory/experiment=<experiment>/`` as a series of
+/// ``part-NNNNN.arrows`` files, so a directory can hold several experiments
+/// with hive-style partitioning. Every result is stored as a
+/// row with its sequence number, UTC timestamp, operation, channels, shape,
+/// values (flattened in row-major order) and metadata as a JSON string.
+/// Rows are buffered and written every ``batch_rows`` results and a new
+/// part is started every ``rows_per_file`` rows; only the current batch is
+/// held in memory, which makes the writer suitable for long retention runs.
+/// Reopening an existing experiment appends new parts instead of
+/// overwriting the existing ones. Parts use the IPC streaming format, so
+/// every batch written so far can be read back with pyarrow or any other
+/// Arrow implementation while the run is still in progress or after a
+/// crash, without waiting for :meth:`~pyarc2.ArrowWriter.close`.
+///
+/// This class is only available if pyarc2 is built with the ``arrow``
+/// feature, which is enabled by default and in the published wheels.
+/// Install the ``arrow`` extra (``pip install pyarc2[arrow]``) to get
+/// pyarrow for reading the files.
+///
+/// >>> with ArrowWriter('runs', 'retention-D5', { 'device': 'D5' }) as w:
+/// ...     w.write(arc.read_slice(5, 0.2), 'read_slice', channels=WORDLINES)
+/// ...     w.drain(arc, DataMode.All)
+/// >>> import glob, pyarrow as pa
+/// >>> parts = sorted(glob.glob('runs/experiment=retention-D5/part-*.arrows'))
+/// >>> table = pa.concat_tables(pa.ipc.open_stream(p).read_all() for p in parts)
+///
+/// :param str directory: The base directory of the dataset
+/// :param str experiment: The name of the experiment
+/// :param dict metadata: Metadata of the experiment stored in the schema of
+///                       every part under the ``pyarc2.metadata`` key
+/// :param int batch_rows: Number of rows to buffer before writing
+/// :param int rows_per_file: Number of rows after which a new part is started
+/// :raises ValueError: If ``experiment`` is not a valid directory name or the
+///                     row counts are zero
+/// :raises IOError: If the experiment directory cannot be created
+#[cfg(feature = "arrow")]
+#[pyclass(name="ArrowWriter", module="pyarc2")]
+struct PyArrowWriter {
+    _directory: PathBuf,
+    _schema: Arc<Schema>,
+    _writer: Option<StreamWriter<BufWriter<File>>>,
+    _pending: Vec<ArrowRow>,
+    _seq: u64,
+    _part: usize,
+    _part_rows: usize,
+    _batch_rows: usize,
+    _rows_per_file: usize
+}
+
+#[cfg(feature = "arrow")]
+impl PyArrowWriter {
+
+    fn schema(experiment: &str, metadata: Option<String>) -> Schema {
+        let mut meta = HashMap::new();
+        meta.insert("pyarc2.experiment".to_string(), experiment.to_string());
+        if let Some(m) = metadata {
+            meta.insert("pyarc2.metadata".to_string(), m);
+        }
+        let item = |t| Field::new("item", t, true);
+
+        Schema::new_with_metadata(vec![
+            Field::new("seq", DataType::UInt64, false),
+            Field::new("timestamp", DataType::Timestamp(TimeUnit::Microsecond,
+                Some("UTC".into())), false),
+            Field::new("operation", DataType::Utf8, false),
+            Field::new_list("channels", item(DataType::UInt32), true),
+            Field::new_list("shape", item(DataType::UInt32), false),
+            Field::new_list("values", item(DataType::Float32), false),
+            Field::new("metadata", DataType::Utf8, true)
+        ], meta)
+    }
+
+    /// First unused part number in ``directory``
+    fn next_part(directory: &Path) -> std::io::Result<usize> {
+        let mut next = 0;
+        for entry in std::fs::read_dir(directory)? {
+            let name = entry?.file_name();
+            let part = name.to_str()
+                .and_then(|n| n.strip_prefix("part-"))
+                .and_then(|n| n.strip_suffix(".arrows"))
+                .and_then(|n| n.parse::<usize>().ok());
+            if let Some(p) = part {
+                next = next.max(p + 1);
+            }
+        }
+        Ok(next)
+    }
+
+    fn part_path(&self) -> PathBuf {
+        self._directory.join(format!("part-{:05}.arrows", self._part))
+    }
+
+    fn push(&mut self, operation: &str, channels: Option<Vec<u32>>, shape: Vec<u32>,
+        values: Vec<f32>, metadata: Option<String>) -> PyResult<()> {
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as i64)
+            .unwrap_or(0);
+
+        self._pending.push(ArrowRow {
+            seq: self._seq,
+            timestamp,
+            operation: operation.to_string(),
+            channels,
+            shape,
+            values,
+            metadata
+        });
+        self._seq += 1;
+
+        if self._pending.len() >= self._batch_rows {
+            self.write_pending()?;
+        }
+
+        Ok(())
+    }
+
+    fn batch(&self) -> Result<RecordBatch, ArrowError> {
+        let rows = self._pending.len();
+        let mut seq = UInt64Builder::with_capacity(rows);
+        let mut timestamp = TimestampMicrosecondBuilder::with_capacity(rows).with_timezone("UTC");
+        let mut operation = StringBuilder::new();
+        let mut channels = ListBuilder::new(UInt32Builder::new());
+        let mut shape = ListBuilder::new(UInt32Builder::new());
+        let mut values = ListBuilder::new(Float32Builder::new());
+        let mut metadata = StringBuilder::new();
+
+        for row in &self._pending {
+            seq.append_value(row.seq);
+            timestamp.append_value(row.timestamp);
+            operation.append_value(&row.operation);
+            match &row.channels {
+                Some(chans) => {
+                    channels.values().append_slice(chans);
+                    channels.append(true);
+                },
+                None => channels.append(false)
+            }
+            shape.values().append_slice(&row.shape);
+            shape.append(true);
+            values.values().append_slice(&row.values);
+            values.append(true);
+            metadata.append_option(row.metadata.as_deref());
+        }
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(seq.finish()),
+            Arc::new(timestamp.finish()),
+            Arc::new(operation.finish()),
+            Arc::new(channels.finish()),
+            Arc::new(shape.finish()),
+            Arc::new(values.finish()),
+            Arc::new(metadata.finish())
+        ];
+
+        RecordBatch::try_new(self._schema.clone(), columns)
+    }
+
+    /// Write all buffered rows to the current part, rotating parts as needed
+    fn write_pending(&mut self) -> PyResult<()> {
+        if self._pending.is_empty() {
+            return Ok(());
+        }
+
+        let batch = self.batch().map_err(arrow_error)?;
+
+        if self._writer.is_none() {
+            let path = self.part_path();
+            let file = File::create(&path).map_err(|err|
+                exceptions::PyIOError::new_err(format!("{}: {}", path.display(), err)))?;
+            let writer = StreamWriter::try_new(BufWriter::new(file), &self._schema)
+                .map_err(arrow_error)?;
+            self._writer = Some(writer);
+        }
+
+        let writer = self._writer.as_mut().unwrap();
+        writer.write(&batch).map_err(arrow_error)?;
+        writer.flush().map_err(arrow_error)?;
+        self._part_rows += self._pending.len();
+        self._pending.clear();
+
+        if self._part_rows >= self._rows_per_file {
+            self.finish_part()?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the end-of-stream marker of the current part; the next batch
+    /// starts a new one
+    fn finish_part(&mut self) -> PyResult<()> {
+        if let Some(mut writer) = self._writer.take() {
+            writer.finish().map_err(arrow_error)?;
+            self._part += 1;
+            self._part_rows = 0;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arrow")]
+#[pymethods]
+impl PyArrowWriter {
+
+    #[new]
+    #[pyo3(signature = (directory, experiment, metadata=None, batch_rows=1024,
+        rows_per_file=1_000_000))]
+    fn new(py: Python, directory: &str, experiment: &str, metadata: Option<&Bound<'_, PyDict>>,
+        batch_rows: usize, rows_per_file: usize) -> PyResult<Self> {
+
+        if experiment.is_empty() || experiment.contains(|c: char| c == '/' || c == '\\') ||
+            experiment == "." || experiment == ".." {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Invalid experiment name: \"{}\"", experiment)));
+        }
+        if batch_rows == 0 || rows_per_file == 0 {
+            return Err(exceptions::PyValueError::new_err(
+                "batch_rows and rows_per_file must be at least 1"));
+        }
+
+        let meta = match metadata {
+            Some(m) => Some(json_dumps(py, m)?),
+            None => None
+        };
+
+        let path = Path::new(directory).join(format!("experiment={}", experiment));
+        let part = std::fs::create_dir_all(&path)
+            .and_then(|_| PyArrowWriter::next_part(&path))
+            .map_err(|err| exceptions::PyIOError::new_err(format!("{}: {}", path.display(), err)))?;
+
+        Ok(PyArrowWriter {
+            _directory: path,
+            _schema: Arc::new(PyArrowWriter::schema(experiment, meta)),
+            _writer: None,
+            _pending: Vec::with_capacity(batch_rows),
+            _seq: 0,
+            _part: part,
+            _part_rows: 0,
+            _batch_rows: batch_rows,
+            _rows_per_file: rows_per_file
+        })
+    }
+
+    /// write(self, data, operation, channels=None, metadata=None, /)
+    /// --
+    ///
+    /// Append a result. ``data`` can be an array of any shape, for instance
+    /// the result of :meth:`~pyarc2.Instrument.read_slice` or
+    /// :meth:`~pyarc2.Instrument.read_all`. For a
+    /// :class:`~pyarc2.Measurement` use its ``data``, ``operation`` and
+    /// ``metadata``.
+    ///
+    /// :param data: A float32 numpy array
+    /// :param str operation: The operation that produced ``data``
+    /// :param channels: The channels ``data`` corresponds to, if any
+    /// :param dict metadata: Parameters of the operation, stored as JSON
+    /// :raises IOError: If a batch could not be written
+    #[pyo3(signature = (data, operation, channels=None, metadata=None))]
+    fn write(&mut self, py: Python, data: PyReadonlyArrayDyn<f32>, operation: &str,
+        channels: Option<Vec<u32>>, metadata: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
+
+        let meta = match metadata {
+            Some(m) => Some(json_dumps(py, m)?),
+            None => None
+        };
+        let shape = data.shape().iter().map(|s| *s as u32).collect();
+        let values = data.as_array().iter().copied().collect();
+
+        self.push(operation, channels, shape, values, meta)
+    }
+
+    /// drain(self, instrument, mode, rtype=None, /)
+    /// --
+    ///
+    /// Append every slab remaining in the long operation buffer of
+    /// ``instrument`` as retrieved by :meth:`~pyarc2.Instrument.pick_one`.
+    /// Each slab is written as a ``pick_one`` row with the channels
+    /// corresponding to ``mode``.
+    ///
+    /// :param instrument: The instrument to read from
+    /// :param mode: A variant of :class:`pyarc2.DataMode`
+    /// :param rtype: A variant of :class:`pyarc2.ReadType`; default is ``Current``
+    /// :return: The number of slabs written
+    /// :raises ArC2Error: If the buffer could not be read
+    /// :raises IOError: If a batch could not be written
+    #[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
+    #[pyo3(signature = (instrument, mode, rtype=None))]
+    fn drain(&mut self, py: Python, mut instrument: PyRefMut<PyInstrument>, mode: PyDataMode,
+        rtype: Option<PyReadType>) -> PyResult<usize> {
+
+        let rtype = rtype.unwrap_or(PyReadType { _inner: ReadType::Current });
+        let chans: Vec<u32> = match mode._inner {
+            DataMode::All => (0..64).collect(),
+            DataMode::Words => crossing_channels(0).into_iter().map(|c| c as u32).collect(),
+            DataMode::Bits => crossing_channels(16).into_iter().map(|c| c as u32).collect()
+        };
+        let meta = serde_json::json!({
+            "mode": data_mode_name(&mode._inner),
+            "rtype": read_type_name(&rtype._inner)
+        }).to_string();
+
+        let mut count = 0;
+        while let Some(array) = instrument.pick_one(py, mode.clone(), rtype.clone())? {
+            let values: Vec<f32> = array.readonly().as_array().to_vec();
+            let shape = vec![values.len() as u32];
+            self.push("pick_one", Some(chans.clone()), shape, values, Some(meta.clone()))?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// flush(self, /)
+    /// --
+    ///
+    /// Write all buffered rows to disk. Flushed rows can be read back
+    /// immediately.
+    ///
+    /// :raises IOError: If the batch could not be written
+    fn flush(&mut self) -> PyResult<()> {
+        self.write_pending()
+    }
+
+    /// close(self, /)
+    /// --
+    ///
+    /// Write all buffered rows and end the current part. Writing after
+    /// closing starts a new part.
+    ///
+    /// :raises IOError: If the part could not be finalised
+    fn close(&mut self) -> PyResult<()> {
+        self.write_pending()?;
+        self.finish_part()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&mut self, _args: &Bound<'_, PyTuple>) -> PyResult<bool> {
+        self.close()?;
+        Ok(false)
+    }
+
+    /// The directory of the experiment
+    #[getter]
+    fn directory(&self) -> String {
+        self._directory.display().to_string()
+    }
+
+    /// Number of rows appended so far, including buffered ones
+    #[getter]
+    fn rows(&self) -> u64 {
+        self._seq
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl Drop for PyArrowWriter {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
 #[pymodule]
 fn pyarc2(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
 
@@ -1724,6 +7139,149 @@ fn pyarc2(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
         }
     }
 
+    /// save_map(path, array, metadata=None, /)
+    /// --
+    ///
+    /// Save a crossbar map, such as the result of
+    /// :meth:`~pyarc2.Instrument.read_all`, along with its metadata. The
+    /// format is selected from the extension of ``path``:
+    ///
+    /// * ``.npz``: a numpy archive with a ``data`` float32 array of shape
+    ///   ``(rows, columns)`` and a ``metadata`` uint8 array holding the
+    ///   metadata as UTF-8 encoded JSON
+    /// * ``.csv``: a ``# pyarc2 map v1`` line, a ``# metadata: `` line
+    ///   followed by the metadata as JSON and one line of comma-separated
+    ///   values per row
+    ///
+    /// Both can be read back with :func:`~pyarc2.load_map` or with numpy
+    /// alone. A :class:`~pyarc2.Measurement` is saved with its own metadata
+    /// unless ``metadata`` is given.
+    ///
+    /// >>> arc.set_structured(True)
+    /// >>> save_map('D5.npz', arc.read_all(0.2, BiasOrder.Rows))
+    /// >>> # or, with structured results disabled
+    /// >>> save_map('D5.csv', arc.read_all(0.2, BiasOrder.Rows), { 'device': 'D5' })
+    ///
+    /// :param str path: The file to write
+    /// :param array: A 2D float32 numpy array or a :class:`~pyarc2.Measurement`
+    ///               holding one
+    /// :param dict metadata: Parameters of the measurement
+    /// :raises ValueError: If the extension of ``path`` is not ``.npz`` or ``.csv``
+    /// :raises IOError: If the file could not be written
+    #[pyfn(m)]
+    #[pyo3(name="save_map", signature = (path, array, metadata=None))]
+    fn py_save_map(py: Python, path: &str, array: &Bound<'_, PyAny>,
+        metadata: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
+        let (data, measured) = if is_measurement(py, array)? {
+            (array.getattr("data")?, Some(array.getattr("metadata")?.downcast_into::<PyDict>()?))
+        } else {
+            (array.clone(), None)
+        };
+        let data: PyReadonlyArray2<f32> = data.extract()?;
+        let meta = export_metadata(py, metadata.or(measured.as_ref()))?;
+        write_map(path, data.as_array(), &meta)
+    }
+
+    /// load_map(path, /)
+    /// --
+    ///
+    /// Load a crossbar map saved with :func:`~pyarc2.save_map`.
+    ///
+    /// :param str path: A ``.npz`` or ``.csv`` file
+    /// :return: A :class:`~pyarc2.Measurement` with the map and its metadata
+    /// :raises ValueError: If the file is not a pyarc2 map
+    /// :raises IOError: If the file could not be read
+    #[pyfn(m)]
+    #[pyo3(name="load_map")]
+    fn py_load_map<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyAny>> {
+        let (data, meta) = read_map(path)?;
+        export_measurement(py, data.into_pyarray_bound(py).into_any(), &meta, None)
+    }
+
+    /// save_trace(path, times, currents=None, metadata=None, /)
+    /// --
+    ///
+    /// Save a current trace, for instance a retention measurement, along
+    /// with its metadata. The format is selected from the extension of
+    /// ``path``:
+    ///
+    /// * ``.npz``: a numpy archive with a ``times`` float64 array in
+    ///   seconds, a ``currents`` float32 array in A and a ``metadata`` uint8
+    ///   array holding the metadata as UTF-8 encoded JSON
+    /// * ``.csv``: a ``# pyarc2 trace v1`` line, a ``# metadata: `` line
+    ///   followed by the metadata as JSON, a ``time,current`` column header
+    ///   and one line per sample
+    ///
+    /// Both can be read back with :func:`~pyarc2.load_trace` or with numpy
+    /// alone. A :class:`~pyarc2.Measurement` with sample times, such as one
+    /// returned by :func:`~pyarc2.load_trace`, can be saved directly in
+    /// place of ``times`` and ``currents``; its own metadata is used unless
+    /// ``metadata`` is given.
+    ///
+    /// >>> save_trace('D5-retention.csv', times, currents, { 'device': 'D5' })
+    /// >>> save_trace('D5-retention.npz', load_trace('D5-retention.csv'))
+    ///
+    /// :param str path: The file to write
+    /// :param times: The time of every sample in seconds or a
+    ///               :class:`~pyarc2.Measurement` with sample times
+    /// :param currents: The current of every sample in A; omitted when
+    ///                  saving a ``Measurement``
+    /// :param dict metadata: Parameters of the measurement
+    /// :raises ValueError: If ``times`` and ``currents`` have different
+    ///                     lengths, ``currents`` is missing or given along
+    ///                     with a ``Measurement``, the ``Measurement`` has no
+    ///                     sample times or the extension of ``path`` is not
+    ///                     ``.npz`` or ``.csv``
+    /// :raises IOError: If the file could not be written
+    #[pyfn(m)]
+    #[pyo3(name="save_trace", signature = (path, times, currents=None, metadata=None))]
+    fn py_save_trace(py: Python, path: &str, times: &Bound<'_, PyAny>, currents: Option<Vec<f32>>,
+        metadata: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
+        let (times, currents, measured): (Vec<f64>, Vec<f32>, _) = if is_measurement(py, times)? {
+            if currents.is_some() {
+                return Err(exceptions::PyValueError::new_err(
+                    "currents must be omitted when saving a Measurement"));
+            }
+            let sampled = times.getattr("times")?;
+            if sampled.is_none() {
+                return Err(exceptions::PyValueError::new_err(
+                    "Measurement has no sample times"));
+            }
+            (sampled.extract()?, times.getattr("data")?.extract()?,
+                Some(times.getattr("metadata")?.downcast_into::<PyDict>()?))
+        } else {
+            match currents {
+                Some(c) => (times.extract()?, c, None),
+                None => return Err(exceptions::PyValueError::new_err(
+                    "currents are required unless a Measurement is saved"))
+            }
+        };
+        if times.len() != currents.len() {
+            return Err(exceptions::PyValueError::new_err(
+                format!("Found {} times but {} currents", times.len(), currents.len())));
+        }
+        let meta = export_metadata(py, metadata.or(measured.as_ref()))?;
+        write_trace(path, &times, &currents, &meta)
+    }
+
+    /// load_trace(path, /)
+    /// --
+    ///
+    /// Load a current trace saved with :func:`~pyarc2.save_trace`.
+    ///
+    /// :param str path: A ``.npz`` or ``.csv`` file
+    /// :return: A :class:`~pyarc2.Measurement` with the currents, their
+    ///          times and the metadata
+    /// :raises ValueError: If the file is not a pyarc2 trace
+    /// :raises IOError: If the file could not be read
+    #[pyfn(m)]
+    #[pyo3(name="load_trace")]
+    fn py_load_trace<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyAny>> {
+        let (times, currents, meta) = read_trace(path)?;
+        export_measurement(py, currents.into_pyarray_bound(py).into_any(), &meta,
+            Some(times.into_pyarray_bound(py).into_any()))
+    }
+
     #[cfg(all(any(target_os = "windows", target_os = "linux"), target_arch = "x86_64"))]
     m.add_class::<PyInstrument>()?;
 
@@ -1734,14 +7292,156 @@ fn pyarc2(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyReadAt>()?;
     m.add_class::<PyReadAfter>()?;
     m.add_class::<PyWaitFor>()?;
+    m.add_class::<PyTriggerEdge>()?;
+    m.add_class::<PyCalibration>()?;
+    m.add_class::<PyChannelReport>()?;
+    m.add_class::<PySelfTestReport>()?;
+    m.add_class::<PyCrosspointReport>()?;
     m.add_class::<PyAuxDACFn>()?;
     m.add_class::<PyIODir>()?;
     m.add_class::<PyLogicLevel>()?;
     m.add_class::<PyOutputRange>()?;
+    m.add_class::<PyWeightMap>()?;
+    m.add_class::<PyMACEncoding>()?;
+    m.add_class::<PySafetyLimits>()?;
+    m.add_class::<PyChannelMode>()?;
+    m.add_class::<PyChannelState>()?;
+    m.add_class::<PySnapshot>()?;
+    #[cfg(feature = "arrow")]
+    m.add_class::<PyArrowWriter>()?;
     m.add("ArC2Error", py.get_type_bound::<ArC2Error>())?;
+    m.add("SafetyLimitError", py.get_type_bound::<SafetyLimitError>())?;
 
     m.setattr(intern!(m.py(), "LIBARC2_VERSION"), libarc2::LIBARC2_VERSION)?;
 
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn weight_map_accepts_distinct_channels() {
+        let map = PyWeightMap::validate(vec![0, 1, 2], vec![(16, None), (17, Some(18))], 1e-6)
+            .unwrap();
+        assert_eq!(map.read_channels(), vec![16, 17, 18]);
+    }
+
+    #[test]
+    fn weight_map_rejects_invalid_layouts() {
+        // no inputs or outputs
+        assert!(PyWeightMap::validate(vec![], vec![(16, None)], 1.0).is_err());
+        assert!(PyWeightMap::validate(vec![0], vec![], 1.0).is_err());
+        // non-positive unit conductance
+        assert!(PyWeightMap::validate(vec![0], vec![(16, None)], 0.0).is_err());
+        assert!(PyWeightMap::validate(vec![0], vec![(16, None)], f32::NAN).is_err());
+        // channels out of range
+        assert!(PyWeightMap::validate(vec![64], vec![(16, None)], 1.0).is_err());
+        assert!(PyWeightMap::validate(vec![0], vec![(16, Some(64))], 1.0).is_err());
+        // channels used twice
+        assert!(PyWeightMap::validate(vec![0, 0], vec![(16, None)], 1.0).is_err());
+        assert!(PyWeightMap::validate(vec![0], vec![(16, Some(16))], 1.0).is_err());
+        assert!(PyWeightMap::validate(vec![0], vec![(16, None), (17, Some(16))], 1.0).is_err());
+        assert!(PyWeightMap::validate(vec![16], vec![(16, None)], 1.0).is_err());
+    }
+
+    #[test]
+    fn dac_codes_are_offset_binary() {
+        assert_eq!(dac_code(0.0, &OutputRange::STD), DAC_ZERO_CODE);
+        assert_eq!(dac_code(-10.0, &OutputRange::STD), 0);
+        assert_eq!(dac_code(10.0, &OutputRange::STD), u16::MAX);
+        assert_eq!(dac_code(-25.0, &OutputRange::EXT), 0);
+        assert_eq!(dac_voltage(0, &OutputRange::EXT), -20.0);
+        assert_eq!(dac_voltage(DAC_ZERO_CODE, &OutputRange::EXT), 0.0);
+        assert_eq!(dac_voltage(DAC_ZERO_CODE + 1, &OutputRange::STD), 0.00030517578125);
+    }
+
+    #[test]
+    fn quantise_rounds_to_the_nearest_code() {
+        assert_eq!(quantise(0.2, &OutputRange::STD), 0.19989013671875);
+        assert_eq!(quantise(1.5, &OutputRange::STD), 1.49993896484375);
+        assert_eq!(quantise(0.0, &OutputRange::EXT), 0.0);
+        // the extended range has twice the step of the standard range
+        assert_eq!(quantise(0.2, &OutputRange::EXT), 0.2001953125);
+        for code in [0u16, 1, 0x7FFF, DAC_ZERO_CODE, 0xFFFF] {
+            let v = dac_voltage(code, &OutputRange::STD);
+            assert_eq!(dac_code(v, &OutputRange::STD), code);
+            assert_eq!(quantise(v, &OutputRange::STD), v);
+        }
+    }
+
+    #[test]
+    fn calibration_survives_json_round_trip() {
+        let offsets: Vec<f32> = (0..64).map(|c| (c as f32) * 1e-9 - 3.2e-8).collect();
+        let gains: Vec<f32> = (0..64).map(|c| 1.0 + (c as f32) * 1e-3).collect();
+        let cal = PyCalibration::new(Some(offsets.clone()), Some(gains.clone())).unwrap();
+
+        let restored = PyCalibration::from_json(&cal.to_json().unwrap()).unwrap();
+        assert_eq!(restored.offsets(), offsets);
+        assert_eq!(restored.gains(), gains);
+        assert_eq!(restored.correct(5, 1e-6), cal.correct(5, 1e-6));
+    }
+
+    #[test]
+    fn calibration_rejects_incomplete_tables() {
+        assert!(PyCalibration::new(Some(vec![0.0; 63]), None).is_err());
+        assert!(PyCalibration::new(None, Some(vec![1.0; 65])).is_err());
+        assert!(PyCalibration::from_json("{ \"offsets\": [0.0], \"gains\": [1.0] }").is_err());
+        assert!(PyCalibration::from_json("{ \"offsets\": [] }").is_err());
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("pyarc2-test-{}-{}", std::process::id(), name))
+            .display().to_string()
+    }
+
+    #[test]
+    fn maps_survive_npz_and_csv_round_trip() {
+        let data = Array2::from_shape_fn((32, 32), |(r, c)| (r as f32 - c as f32) * 1.25e-9);
+        let metadata = "{\"operation\": \"read_all\", \"vread\": 0.2}";
+
+        for ext in ["npz", "csv"] {
+            let path = temp_path(&format!("map.{}", ext));
+            write_map(&path, data.view(), metadata).unwrap();
+            let (loaded, meta) = read_map(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(loaded, data);
+            assert_eq!(meta, metadata);
+        }
+    }
+
+    #[test]
+    fn traces_survive_npz_and_csv_round_trip() {
+        let times: Vec<f64> = (0..100).map(|t| (t as f64) * 0.1).collect();
+        let currents: Vec<f32> = (0..100).map(|t| 1e-6 / (1.0 + t as f32)).collect();
+
+        for ext in ["npz", "csv"] {
+            let path = temp_path(&format!("trace.{}", ext));
+            write_trace(&path, &times, &currents, "{}").unwrap();
+            let (t, i, meta) = read_trace(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(t, times);
+            assert_eq!(i, currents);
+            assert_eq!(meta, "{}");
+        }
+    }
+
+    #[test]
+    fn exports_reject_unknown_files() {
+        assert!(write_map(&temp_path("map.txt"), Array2::zeros((2, 2)).view(), "{}").is_err());
+
+        let path = temp_path("noheader.csv");
+        std::fs::write(&path, format!("{}\n{}{{}}\n0.0,1e-6\n", TRACE_CSV_TAG, CSV_METADATA))
+            .unwrap();
+        let trace = read_trace(&path);
+        std::fs::write(&path, format!("{}\n0.0,1e-6\n", MAP_CSV_TAG)).unwrap();
+        let map_as_trace = read_trace(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(trace.is_err());
+        assert!(map_as_trace.is_err());
+    }
+}
@@ -0,0 +1,66 @@
+import numpy as np
+import pytest
+from pyarc2 import Measurement, save_map, load_map, save_trace, load_trace
+
+
+@pytest.mark.parametrize('ext', ['npz', 'csv'])
+def test_map_round_trip(tmp_path, ext):
+    path = str(tmp_path / ('map.' + ext))
+    data = np.arange(32 * 32, dtype=np.float32).reshape(32, 32) * 1e-9
+    save_map(path, data, { 'operation': 'read_all', 'vread': 0.2 })
+
+    m = load_map(path)
+    assert isinstance(m, Measurement)
+    assert m.shape == (32, 32)
+    assert m.dtype == np.float32
+    np.testing.assert_array_equal(m.data, data)
+    assert m.metadata == { 'operation': 'read_all', 'vread': 0.2 }
+
+
+@pytest.mark.parametrize('ext', ['npz', 'csv'])
+def test_measurement_map_keeps_its_metadata(tmp_path, ext):
+    path = str(tmp_path / ('map.' + ext))
+    m = Measurement(np.ones((4, 8), dtype=np.float32), { 'device': 'D5' })
+
+    save_map(path, m)
+    assert load_map(path).metadata == { 'device': 'D5' }
+    save_map(path, m, { 'device': 'D6' })
+    assert load_map(path).metadata == { 'device': 'D6' }
+
+
+@pytest.mark.parametrize('ext', ['npz', 'csv'])
+def test_trace_round_trip(tmp_path, ext):
+    path = str(tmp_path / ('trace.' + ext))
+    times = np.linspace(0.0, 10.0, 101)
+    currents = (1e-6 / (1.0 + times)).astype(np.float32)
+    save_trace(path, times, currents, { 'device': 'D5' })
+
+    m = load_trace(path)
+    np.testing.assert_array_equal(m.times, times)
+    np.testing.assert_array_equal(m.data, currents)
+    assert m.metadata == { 'device': 'D5' }
+
+    # a loaded trace can be saved again directly
+    other = str(tmp_path / ('copy.' + ext))
+    save_trace(other, m)
+    copy = load_trace(other)
+    np.testing.assert_array_equal(copy.times, times)
+    np.testing.assert_array_equal(copy.data, currents)
+    assert copy.metadata == { 'device': 'D5' }
+
+
+def test_invalid_traces(tmp_path):
+    path = str(tmp_path / 'trace.csv')
+    with pytest.raises(ValueError):
+        save_trace(path, [0.0, 1.0], [1e-6])
+    with pytest.raises(ValueError):
+        save_trace(path, [0.0, 1.0])
+    with pytest.raises(ValueError):
+        save_trace(path, Measurement(np.zeros(2, dtype=np.float32)))
+    with pytest.raises(ValueError):
+        save_trace(str(tmp_path / 'trace.txt'), [0.0], [1e-6])
+
+    with open(path, 'w') as fh:
+        fh.write('# pyarc2 trace v1\n# metadata: {}\n0.0,1e-6\n')
+    with pytest.raises(ValueError):
+        load_trace(path)
@@ -0,0 +1,123 @@
+import json
+import numpy as np
+import pytest
+from pyarc2 import Instrument, Measurement, read_journal
+from pyarc2 import DataMode, ReadType, ReadAt, WaitFor, ControlMode, LogicLevel, TriggerEdge
+from pyarc2._journal import encode, decode
+
+
+class Opaque:
+    pass
+
+
+class FakeInstrument:
+
+    start_journal = Instrument.start_journal
+    stop_journal = Instrument.stop_journal
+
+    def __init__(self):
+        self._journal = None
+
+    def port(self):
+        return 1
+
+    def firmware(self):
+        return 'fw.bin'
+
+    def read_one(self, low, high, vread):
+        return 1e-6
+
+    def nested(self):
+        return self.read_one(0, 16, 0.2)
+
+    def execute(self):
+        return self
+
+    def fail(self):
+        raise ValueError('bad argument')
+
+
+def _roundtrip(value):
+    return decode(json.loads(json.dumps(encode(value))))
+
+
+def test_arrays_keep_dtype_and_shape():
+    for arr in (np.arange(6, dtype=np.uint64).reshape(2, 3),
+        np.linspace(0, 1, 4, dtype=np.float32), np.zeros((0, 2), dtype=np.float64)):
+        out = _roundtrip(arr)
+        assert out.dtype == arr.dtype
+        assert out.shape == arr.shape
+        np.testing.assert_array_equal(out, arr)
+
+
+def test_containers_and_scalars():
+    assert _roundtrip(np.float32(0.5)) == 0.5
+    assert _roundtrip([1, 'a', None, True, [2.5]]) == [1, 'a', None, True, [2.5]]
+    assert _roundtrip((1, 2)) == [1, 2]
+    assert _roundtrip({ 3: 'x', (0, 16): 0.2, 'k': [1] }) == { 3: 'x', (0, 16): 0.2, 'k': [1] }
+
+
+def test_measurements():
+    m = Measurement(np.arange(4, dtype=np.float32), { 'operation': 'read_slice', 'vread': 0.2 },
+        times=np.array([0.0, 1.0, 2.0, 3.0]))
+    out = _roundtrip(m)
+    assert isinstance(out, Measurement)
+    np.testing.assert_array_equal(out.data, m.data)
+    np.testing.assert_array_equal(out.times, m.times)
+    assert out.metadata == m.metadata
+
+
+def test_directives_are_encoded_by_name():
+    values = [DataMode.Words, ReadType.Voltage, ReadAt.Arb(0.5), ReadAt.Bias,
+        WaitFor.Millis(2), WaitFor.Trigger(3, TriggerEdge.Falling),
+        ControlMode.Internal, LogicLevel.LL5V]
+    encoded = [encode(v) for v in values]
+    assert [e['str'] for e in encoded] == ['DataMode.Words', 'ReadType.Voltage',
+        'ReadAt.Arb(0.5)', 'ReadAt.Bias', 'WaitFor.Nanos(2000000)',
+        'WaitFor.Trigger(3, TriggerEdge.Falling)', 'ControlMode.Internal', 'LogicLevel.LL5V']
+    assert encoded == [encode(v) for v in values]
+
+
+def test_objects_are_never_encoded_by_address():
+    assert encode(Opaque()) == { '__object__': 'Opaque' }
+    assert encode(Opaque()) == encode(Opaque())
+
+
+def test_journal_records_outermost_calls(tmp_path):
+    path = str(tmp_path / 'session.jsonl')
+
+    arc = FakeInstrument()
+    arc.start_journal(path)
+    assert isinstance(arc, FakeInstrument)
+    assert arc.execute() is arc
+    arc.nested()
+    with pytest.raises(ValueError):
+        arc.fail()
+    arc.stop_journal()
+    assert type(arc) is FakeInstrument
+    arc.read_one(0, 16, 0.2)
+
+    sessions = read_journal(path)
+    assert len(sessions) == 1
+    assert sessions[0]['header']['port'] == 1
+    assert sessions[0]['header']['firmware'] == 'fw.bin'
+
+    entries = sessions[0]['entries']
+    assert [e['command'] for e in entries] == ['execute', 'nested', 'fail']
+    assert [e['seq'] for e in entries] == [0, 1, 2]
+    assert entries[0]['result'] == { '__self__': True }
+    assert entries[1]['result'] == 1e-6
+    assert entries[2]['error'] == { 'type': 'ValueError', 'message': 'bad argument' }
+
+
+def test_journals_append_sessions(tmp_path):
+    path = str(tmp_path / 'session.jsonl')
+
+    arc = FakeInstrument()
+    for vread in (0.1, 0.2):
+        arc.start_journal(path)
+        arc.read_one(0, 16, vread)
+        arc.stop_journal()
+
+    sessions = read_journal(path)
+    assert [s['entries'][0]['args'] for s in sessions] == [[0, 16, 0.1], [0, 16, 0.2]]
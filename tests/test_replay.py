@@ -0,0 +1,102 @@
+import numpy as np
+import pytest
+from pyarc2 import Instrument, ReplayInstrument, ReplayError, SafetyLimitError
+from pyarc2 import DataMode, ReadType, ReadAt, WaitFor, ControlMode, LogicLevel
+
+
+class FakeInstrument:
+    """
+    Stands in for an ``Instrument`` connected to hardware; the journaling
+    methods are borrowed from :class:`~pyarc2.Instrument`.
+    """
+
+    start_journal = Instrument.start_journal
+    stop_journal = Instrument.stop_journal
+
+    def __init__(self):
+        self._journal = None
+        self._nreads = 0
+
+    def port(self):
+        return 0
+
+    def firmware(self):
+        return 'fw.bin'
+
+    def set_control_mode(self, mode):
+        return self
+
+    def set_logic_level(self, level):
+        return self
+
+    def read_slice(self, chan, vread):
+        return np.linspace(0.0, vread, 32, dtype=np.float32)
+
+    def wait_for(self, condition, timeout=None):
+        return self
+
+    def read_at(self, mode, rtype, at):
+        return { 'mode': str(mode), 'rtype': str(rtype), 'at': at.voltage() }
+
+    def pick_one(self, mode, rtype):
+        self._nreads += 1
+        if self._nreads > 2:
+            return None
+        return np.full(64, self._nreads, dtype=np.float32)
+
+    def fail(self):
+        raise SafetyLimitError('Voltage exceeds the limit')
+
+
+def _session(arc):
+    results = []
+    results.append(arc.set_control_mode(ControlMode.Header))
+    results.append(arc.set_logic_level(LogicLevel.LL3V3))
+    results.append(arc.read_slice(3, 0.2))
+    results.append(arc.wait_for(WaitFor.Millis(10), timeout=100))
+    results.append(arc.read_at(DataMode.All, ReadType.Current, ReadAt.Arb(0.2)))
+    results.append(arc.read_slice(4, float('nan')))
+    for _ in range(3):
+        results.append(arc.pick_one(DataMode.Bits, ReadType.Voltage))
+    with pytest.raises(SafetyLimitError):
+        arc.fail()
+    return results
+
+
+def test_strict_replay_of_recorded_session(tmp_path):
+    path = str(tmp_path / 'session.jsonl')
+
+    arc = FakeInstrument()
+    arc.start_journal(path)
+    recorded = _session(arc)
+    arc.stop_journal()
+    assert type(arc) is FakeInstrument
+
+    replay = ReplayInstrument(path)
+    assert replay.firmware() == 'fw.bin'
+    replayed = _session(replay)
+    assert replay.remaining() == 0
+
+    assert len(recorded) == len(replayed)
+    for (rec, rep) in zip(recorded, replayed):
+        if rec is arc:
+            assert rep is replay
+        elif isinstance(rec, np.ndarray):
+            assert rep.dtype == rec.dtype
+            np.testing.assert_array_equal(rep, rec)
+        else:
+            assert rep == rec
+
+
+def test_replay_rejects_different_arguments(tmp_path):
+    path = str(tmp_path / 'session.jsonl')
+
+    arc = FakeInstrument()
+    arc.start_journal(path)
+    arc.set_control_mode(ControlMode.Header)
+    arc.stop_journal()
+
+    with pytest.raises(ReplayError):
+        ReplayInstrument(path).set_control_mode(ControlMode.Internal)
+    assert ReplayInstrument(path, strict=False).set_control_mode(ControlMode.Internal) \
+        is not None